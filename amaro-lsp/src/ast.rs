@@ -16,34 +16,102 @@ pub fn next_node_id() -> NodeId {
 // }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NodeId(pub u32);
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AmaroFile {
     pub blocks: Vec<Block>,
+    /// Top-level `let` bindings, in source order.
+    pub bindings: Vec<LetDef>,
+    /// Top-level embedded `{{ ... }}` Rust blocks, in source order.
+    pub rust_blocks: Vec<RustBlock>,
+    /// Recoverable syntax errors found while parsing block bodies.
+    pub syntax_errors: Vec<SyntaxError>,
+    /// The file exceeded `AnalysisConfig::max_file_bytes`, so only block
+    /// headers were parsed: every block is empty and there are no bindings.
+    pub outline_only: bool,
     pub id: NodeId,
 }
 
+impl AmaroFile {
+    /// Returns every top-level expression: block field values, then the
+    /// values of top-level `let` bindings.
+    pub fn values(&self) -> impl Iterator<Item = &Expr> {
+        self.blocks
+            .iter()
+            .flat_map(|block| {
+                let BlockContent::Fields(items) = &block.content;
+                items.iter().filter_map(|item| match item {
+                    BlockItem::Field(field) => Some(&field.value),
+                    BlockItem::StructDef(_) => None,
+                })
+            })
+            .chain(self.bindings.iter().map(|binding| &binding.value))
+    }
+}
+
+/// A syntax error the parser recovered from, located in the source.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SyntaxError {
+    pub message: String,
+    pub range: Range,
+}
+
+/// A syntax error the parser could not recover from.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParseError {
+    pub message: String,
+    /// Byte offset where recovery gave up.
+    pub offset: usize,
+    pub range: Range,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message,
+            self.range.start.line + 1,
+            self.range.start.character + 1
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Block {
     pub kind: String,
+    /// Range of the block's kind identifier.
     pub range: Range,
+    /// Range of the whole block, from its kind to the closing `]` or the
+    /// last non-blank line of a colon block.
+    pub outer_range: Range,
     pub content: BlockContent,
     pub id: NodeId,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BlockContent {
     Fields(Vec<BlockItem>),
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BlockItem {
     Field(Field),
     StructDef(StructDef),
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Field {
     pub key: String,
     pub key_range: Range,
@@ -52,7 +120,29 @@ pub struct Field {
     pub id: NodeId,
 }
 
+/// An embedded `{{ ... }}` Rust block. `range` covers the Rust code
+/// between the braces.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RustBlock {
+    pub code: String,
+    pub range: Range,
+    pub id: NodeId,
+}
+
+/// A top-level `let name = value` binding, visible to every block.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LetDef {
+    pub name: String,
+    pub name_range: Range,
+    pub value: Expr,
+    pub range: Range,
+    pub id: NodeId,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct StructDef {
     pub name: String,
     pub name_range: Range,
@@ -62,6 +152,7 @@ pub struct StructDef {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TypedParam {
     pub name: String,
     pub type_annotation: TypeAnnotation,
@@ -70,6 +161,7 @@ pub struct TypedParam {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TypeAnnotation {
     Simple(String),
     Generic(String, Vec<TypeAnnotation>),
@@ -81,6 +173,7 @@ pub enum TypeAnnotation {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Expr {
     pub kind: ExprKind,
     pub range: Range,
@@ -88,6 +181,7 @@ pub struct Expr {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ExprKind {
     // Literals
     Identifier(String),
@@ -140,6 +234,7 @@ pub enum ExprKind {
     // Let binding
     LetBinding {
         name: String,
+        name_range: Range,
         value: Box<Expr>,
         body: Box<Expr>,
     },
@@ -174,6 +269,7 @@ pub enum ExprKind {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BinaryOperator {
     // Arithmetic
     Add,
@@ -181,6 +277,7 @@ pub enum BinaryOperator {
     Mul,
     Div,
     Mod,
+    Pow,
 
     // Comparison
     Eq,
@@ -201,7 +298,44 @@ pub enum BinaryOperator {
     Tensor, // ⊗
 }
 
+impl BinaryOperator {
+    /// The operator as written in source.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Sub => "-",
+            BinaryOperator::Mul => "*",
+            BinaryOperator::Div => "/",
+            BinaryOperator::Mod => "%",
+            BinaryOperator::Pow => "**",
+            BinaryOperator::Eq => "==",
+            BinaryOperator::Ne => "!=",
+            BinaryOperator::Lt => "<",
+            BinaryOperator::Le => "<=",
+            BinaryOperator::Gt => ">",
+            BinaryOperator::Ge => ">=",
+            BinaryOperator::And => "&&",
+            BinaryOperator::Or => "||",
+            BinaryOperator::Range => "..",
+            BinaryOperator::Tensor => "⊗",
+        }
+    }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::Eq
+                | BinaryOperator::Ne
+                | BinaryOperator::Lt
+                | BinaryOperator::Le
+                | BinaryOperator::Gt
+                | BinaryOperator::Ge
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum UnaryOperator {
     Not,
     Neg,
@@ -236,6 +370,37 @@ impl Expr {
         Expr::new(ExprKind::BoolLiteral(value), range)
     }
 
+    /// Returns the direct sub-expressions of this expression, in source order.
+    pub fn children(&self) -> Vec<&Expr> {
+        match &self.kind {
+            ExprKind::Identifier(_)
+            | ExprKind::IntLiteral(_)
+            | ExprKind::FloatLiteral(_)
+            | ExprKind::StringLiteral(_)
+            | ExprKind::BoolLiteral(_)
+            | ExprKind::None => Vec::new(),
+            ExprKind::List(items) | ExprKind::Tuple(items) => items.iter().collect(),
+            ExprKind::StructLiteral { fields, .. } => fields.iter().map(|(_, v)| v).collect(),
+            ExprKind::FunctionCall { function, args } => {
+                std::iter::once(function.as_ref()).chain(args).collect()
+            }
+            ExprKind::FieldAccess { object, .. } => vec![object],
+            ExprKind::IndexAccess { object, index } => vec![object, index],
+            ExprKind::Lambda { body, .. } => vec![body],
+            ExprKind::IfThenElse {
+                condition,
+                then_branch,
+                else_branch,
+            } => vec![condition, then_branch, else_branch],
+            ExprKind::LetBinding { value, body, .. } => vec![value, body],
+            ExprKind::BinaryOp { left, right, .. } => vec![left, right],
+            ExprKind::UnaryOp { operand, .. } => vec![operand],
+            ExprKind::Some(inner) => vec![inner],
+            ExprKind::TensorProduct { left, right } => vec![left, right],
+            ExprKind::Projection { tuple, .. } => vec![tuple],
+        }
+    }
+
     pub fn summarize(&self) -> String {
         self.summarize_with_limit(50)
     }
@@ -318,14 +483,24 @@ impl Expr {
 }
 
 impl Block {
-    pub fn new(kind: String, range: Range, content: BlockContent) -> Self {
+    pub fn new(kind: String, range: Range, outer_range: Range, content: BlockContent) -> Self {
         Block {
             kind,
             range,
+            outer_range,
             content,
             id: next_node_id(),
         }
     }
+
+    /// Returns the first field named `key` in this block.
+    pub fn field(&self, key: &str) -> Option<&Field> {
+        let BlockContent::Fields(items) = &self.content;
+        items.iter().find_map(|item| match item {
+            BlockItem::Field(field) if field.key == key => Some(field),
+            _ => None,
+        })
+    }
 }
 
 impl Field {
@@ -340,6 +515,28 @@ impl Field {
     }
 }
 
+impl LetDef {
+    pub fn new(name: String, name_range: Range, value: Expr, range: Range) -> Self {
+        LetDef {
+            name,
+            name_range,
+            value,
+            range,
+            id: next_node_id(),
+        }
+    }
+}
+
+impl RustBlock {
+    pub fn new(code: String, range: Range) -> Self {
+        RustBlock {
+            code,
+            range,
+            id: next_node_id(),
+        }
+    }
+}
+
 impl StructDef {
     pub fn new(name: String, name_range: Range, fields: Vec<TypedParam>, range: Range) -> Self {
         StructDef {
@@ -367,7 +564,23 @@ impl AmaroFile {
     pub fn new(blocks: Vec<Block>) -> Self {
         AmaroFile {
             blocks,
+            bindings: Vec::new(),
+            rust_blocks: Vec::new(),
+            syntax_errors: Vec::new(),
+            outline_only: false,
             id: next_node_id(),
         }
     }
+
+    /// Returns the first block of the given kind, matched exactly.
+    pub fn block(&self, kind: &str) -> Option<&Block> {
+        self.blocks.iter().find(|block| block.kind == kind)
+    }
+}
+
+/// Serializes `file` to JSON, for external tooling such as visualizers and
+/// snapshot tests.
+#[cfg(feature = "serde")]
+pub fn to_json(file: &AmaroFile) -> String {
+    serde_json::to_string(file).expect("AST contains only serializable data")
 }
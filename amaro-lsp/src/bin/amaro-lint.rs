@@ -0,0 +1,108 @@
+//! Command-line linter for `.qmrl` files.
+//!
+//! Runs the same diagnostics as the language server and prints them as
+//! `path:line:col: severity: message` (or a JSON array with `--format json`).
+//! Exits with 1 if any error was reported and 2 on usage or I/O failures.
+
+use std::process::ExitCode;
+
+use amaro_lsp::diagnose;
+use serde_json::json;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
+
+const USAGE: &str = "usage: amaro-lint [--format text|json] FILE...";
+
+enum Format {
+    Text,
+    Json,
+}
+
+fn severity_name(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        Some(DiagnosticSeverity::HINT) => "hint",
+        _ => "error",
+    }
+}
+
+fn is_error(diagnostic: &Diagnostic) -> bool {
+    diagnostic
+        .severity
+        .is_none_or(|s| s == DiagnosticSeverity::ERROR)
+}
+
+fn main() -> ExitCode {
+    let mut format = Format::Text;
+    let mut paths = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => match args.next().as_deref() {
+                Some("text") => format = Format::Text,
+                Some("json") => format = Format::Json,
+                _ => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    if paths.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+
+    let mut found_error = false;
+    let mut json_entries = Vec::new();
+
+    for path in &paths {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                return ExitCode::from(2);
+            }
+        };
+
+        for diagnostic in diagnose(&source) {
+            found_error |= is_error(&diagnostic);
+            let start = diagnostic.range.start;
+            match format {
+                Format::Text => println!(
+                    "{}:{}:{}: {}: {}",
+                    path,
+                    start.line + 1,
+                    start.character + 1,
+                    severity_name(diagnostic.severity),
+                    diagnostic.message
+                ),
+                Format::Json => json_entries.push(json!({
+                    "path": path,
+                    "line": start.line + 1,
+                    "column": start.character + 1,
+                    "range": diagnostic.range,
+                    "severity": severity_name(diagnostic.severity),
+                    "message": diagnostic.message,
+                })),
+            }
+        }
+    }
+
+    if let Format::Json = format {
+        println!("{}", serde_json::Value::Array(json_entries));
+    }
+
+    if found_error {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Diagnostic, Range, TextEdit, Url,
+    WorkspaceEdit,
+};
+
+use crate::ast::{AmaroFile, Block};
+use crate::parser::AnalysisConfig;
+use crate::parser::config::rule_code;
+use crate::parser::semantics::{MANDATORY_BLOCKS, required_fields};
+use crate::parser::utils::{calc_range, position_to_byte};
+
+/// Collects the code actions available for `range` in the document.
+///
+/// `diagnostics` are the diagnostics the client sent along with the request;
+/// matching ones are attached to the quick fixes that resolve them. `config`
+/// is the one the diagnostics were produced with, so the fixes agree with them.
+pub fn code_actions(
+    uri: &Url,
+    text: &str,
+    file: &AmaroFile,
+    range: Range,
+    diagnostics: &[Diagnostic],
+    config: &AnalysisConfig,
+) -> Vec<CodeActionOrCommand> {
+    let mut actions = Vec::new();
+
+    // Block Capitalization Fixes
+    for block in &file.blocks {
+        let Some(correct_name) = config.canonical_block_name(&block.kind) else {
+            continue;
+        };
+        if block.kind == correct_name || !ranges_overlap(block.range, range) {
+            continue;
+        }
+
+        let fixed: Vec<Diagnostic> = diagnostics
+            .iter()
+            .filter(|d| d.range == block.range && d.code == rule_code("capitalization"))
+            .cloned()
+            .collect();
+
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Rename '{}' to '{}'", block.kind, correct_name),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: if fixed.is_empty() { None } else { Some(fixed) },
+            edit: Some(single_edit(uri, block.range, correct_name.to_string())),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    // Missing Mandatory Block Fixes
+    for kind in MANDATORY_BLOCKS {
+        let message = format!("Missing mandatory block: '{}'", kind);
+        let fixed: Vec<Diagnostic> = diagnostics
+            .iter()
+            .filter(|d| d.message.contains(&message))
+            .cloned()
+            .collect();
+        if fixed.is_empty() {
+            continue;
+        }
+
+        let separator = if text.is_empty() {
+            ""
+        } else if text.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        let end = calc_range(text, text.len(), 0);
+
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Add missing '{}' block", kind),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(fixed),
+            edit: Some(single_edit(
+                uri,
+                end,
+                format!("{}{}", separator, block_skeleton(kind)),
+            )),
+            ..Default::default()
+        }));
+    }
+
+    // Bracket To Colon Block Refactors
+    for block in &file.blocks {
+        if !ranges_overlap(block.outer_range, range) {
+            continue;
+        }
+        let Some(colon_block) = bracket_to_colon(text, block) else {
+            continue;
+        };
+
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Convert '{}' to a colon block", block.kind),
+            kind: Some(CodeActionKind::REFACTOR_REWRITE),
+            edit: Some(single_edit(uri, block.outer_range, colon_block)),
+            ..Default::default()
+        }));
+    }
+
+    actions
+}
+
+/// Rewrites a closed `Kind[ ... ]` block as `Kind:` with its lines indented
+/// by four spaces. Returns `None` for colon blocks and unclosed brackets.
+fn bracket_to_colon(text: &str, block: &Block) -> Option<String> {
+    let start = position_to_byte(text, block.range.end);
+    let end = position_to_byte(text, block.outer_range.end);
+    let inner = text
+        .get(start..end)?
+        .trim_start()
+        .strip_prefix('[')?
+        .strip_suffix(']')?;
+
+    let mut colon_block = format!("{}:", block.kind);
+
+    // Text on the bracket's own line has no indentation of its own; the
+    // remaining lines keep their indentation relative to each other.
+    let (first, rest) = inner.split_once('\n').unwrap_or((inner, ""));
+    let rest: Vec<&str> = rest.lines().map(|line| line.trim_end()).collect();
+    let indent = rest
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let lines: Vec<&str> = std::iter::once(first.trim())
+        .chain(rest.iter().map(|line| line.get(indent..).unwrap_or("")))
+        .collect();
+    // Blank lines between fields are kept, but not around them
+    let body = lines
+        .iter()
+        .position(|line| !line.is_empty())
+        .and_then(|first| Some(first..=lines.iter().rposition(|line| !line.is_empty())?));
+    for line in body.map_or(&[][..], |body| &lines[body]) {
+        colon_block.push('\n');
+        if !line.is_empty() {
+            colon_block.push_str("    ");
+            colon_block.push_str(line);
+        }
+    }
+
+    // Anything after the `]` on the same line must not join the block
+    let after = &text[end..];
+    if !after.split('\n').next().unwrap_or("").trim().is_empty() {
+        colon_block.push('\n');
+    }
+    Some(colon_block)
+}
+
+/// Builds an empty colon block listing the block's required fields.
+fn block_skeleton(kind: &str) -> String {
+    let mut skeleton = format!("{}:\n", kind);
+    for field in required_fields(kind) {
+        skeleton.push_str(&format!("    {} = \n", field));
+    }
+    skeleton
+}
+
+fn single_edit(uri: &Url, range: Range, new_text: String) -> WorkspaceEdit {
+    let mut changes = HashMap::new();
+    changes.insert(uri.clone(), vec![TextEdit { range, new_text }]);
+    WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    }
+}
+
+fn ranges_overlap(a: Range, b: Range) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
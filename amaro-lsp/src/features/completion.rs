@@ -0,0 +1,142 @@
+use tower_lsp::lsp_types::{CompletionItem, CompletionItemKind, InsertTextFormat, Position};
+
+use super::hover::format_signature;
+use crate::ast::AmaroFile;
+use crate::parser::AnalysisConfig;
+use crate::parser::expr::parse_expr;
+use crate::parser::semantics::{file_globals, infer_expr_type};
+use crate::parser::symbols::{SymbolTable, Type, members};
+use crate::parser::utils::position_to_byte;
+
+/// Collects completion items for the cursor position.
+///
+/// After a `.`, offers the members of the type the expression before it has;
+/// `file`, when parsed, supplies struct definitions and top-level bindings.
+/// Elsewhere, offers the block kinds `config` knows, built-in functions, and
+/// gate literals.
+pub fn completions_at(
+    text: &str,
+    file: Option<&AmaroFile>,
+    position: Position,
+    config: &AnalysisConfig,
+) -> Vec<CompletionItem> {
+    let offset = position_to_byte(text, position);
+    if let Some(dot) = dot_before(text, offset) {
+        return member_completions(text, file, dot);
+    }
+
+    let mut items: Vec<CompletionItem> = config
+        .known_blocks
+        .iter()
+        .map(|kind| CompletionItem {
+            label: kind.to_string(),
+            kind: Some(CompletionItemKind::CLASS),
+            detail: Some("block".to_string()),
+            ..Default::default()
+        })
+        .collect();
+
+    let mut functions: Vec<_> = SymbolTable::builtin_functions().into_iter().collect();
+    functions.sort_by(|a, b| a.0.cmp(&b.0));
+    items.extend(
+        functions
+            .iter()
+            .map(|(name, ty)| function_completion(name, ty)),
+    );
+
+    let mut gates: Vec<_> = SymbolTable::gate_literals().into_iter().collect();
+    gates.sort_by(|a, b| a.0.cmp(&b.0));
+    items.extend(gates.into_iter().map(|(gate, ty)| CompletionItem {
+        label: gate,
+        kind: Some(CompletionItemKind::CONSTANT),
+        detail: Some(ty.to_string()),
+        ..Default::default()
+    }));
+
+    items
+}
+
+fn function_completion(name: &str, ty: &Type) -> CompletionItem {
+    let placeholders = match ty {
+        Type::Function { params, .. } => params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| format!("${{{}:{}}}", i + 1, param))
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => String::new(),
+    };
+
+    CompletionItem {
+        label: name.to_string(),
+        kind: Some(CompletionItemKind::FUNCTION),
+        detail: Some(format_signature(name, ty)),
+        insert_text: Some(format!("{}({})", name, placeholders)),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        ..Default::default()
+    }
+}
+
+/// Offers the members of the expression ending just before the `.` at `dot`.
+fn member_completions(text: &str, file: Option<&AmaroFile>, dot: usize) -> Vec<CompletionItem> {
+    let base = base_expression(text, dot);
+    if base.is_empty() {
+        return Vec::new();
+    }
+    let Ok((_, expr)) = parse_expr(base, base, &mut Vec::new()) else {
+        return Vec::new();
+    };
+
+    let mut diagnostics = Vec::new();
+    let mut table = SymbolTable::new();
+    if let Some(file) = file {
+        for (name, ty) in file_globals(file, &mut diagnostics) {
+            table.bind(name, ty);
+        }
+    }
+    let base_type = infer_expr_type(&expr, &mut table, &mut diagnostics);
+
+    members(&base_type)
+        .into_iter()
+        .map(|(name, ty)| CompletionItem {
+            kind: Some(if matches!(ty, Type::Function { .. }) {
+                CompletionItemKind::METHOD
+            } else {
+                CompletionItemKind::FIELD
+            }),
+            detail: Some(format_signature(&name, &ty)),
+            label: name,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Returns the offset of the `.` that the identifier being typed at
+/// `offset` follows, if any.
+fn dot_before(text: &str, offset: usize) -> Option<usize> {
+    let before = text[..offset].trim_end_matches(|c: char| c.is_ascii_alphanumeric() || c == '_');
+    before.strip_suffix('.').map(str::len)
+}
+
+/// The member-access chain ending at `end`, such as `State.map()` or
+/// `Gate.qubits[0]`: identifiers and dots, plus balanced `(...)` and `[...]`.
+fn base_expression(text: &str, end: usize) -> &str {
+    let bytes = text.as_bytes();
+    let mut start = end;
+    let mut depth = 0usize;
+
+    while start > 0 {
+        let b = bytes[start - 1];
+        match b {
+            b')' | b']' => depth += 1,
+            b'(' | b'[' if depth > 0 => depth -= 1,
+            _ if depth > 0 => {}
+            b'.' | b'_' => {}
+            _ if b.is_ascii_alphanumeric() => {}
+            _ => break,
+        }
+        start -= 1;
+    }
+
+    if depth > 0 { "" } else { &text[start..end] }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DocumentDiagnosticReport,
+    FullDocumentDiagnosticReport, Location, Range, RelatedFullDocumentDiagnosticReport,
+    RelatedUnchangedDocumentDiagnosticReport, UnchangedDocumentDiagnosticReport, Url,
+};
+
+use crate::ast::{AmaroFile, ParseError};
+use crate::parser::{AnalysisConfig, diagnose_parsed};
+
+/// Builds a pull-diagnostics report for a document.
+///
+/// The result id is a hash of `text`; when it matches `previous_result_id`
+/// the client already has these diagnostics and gets an `Unchanged` report.
+pub fn diagnostic_report(
+    text: &str,
+    parsed: &Result<AmaroFile, ParseError>,
+    config: &AnalysisConfig,
+    previous_result_id: Option<&str>,
+) -> DocumentDiagnosticReport {
+    let result_id = result_id(text);
+
+    if previous_result_id == Some(result_id.as_str()) {
+        return DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+            related_documents: None,
+            unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+        });
+    }
+
+    DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+        related_documents: None,
+        full_document_diagnostic_report: FullDocumentDiagnosticReport {
+            result_id: Some(result_id),
+            items: diagnose_parsed(parsed, config),
+        },
+    })
+}
+
+/// Reports block kinds defined in more than one of `files`.
+///
+/// Each file gets an ERROR on its first block of a shared kind, with related
+/// information pointing at that kind's block in every other file. Kinds are
+/// compared case-insensitively, like the single-file duplicate check.
+pub fn cross_file_duplicates(files: &[(&Url, &AmaroFile)]) -> HashMap<Url, Vec<Diagnostic>> {
+    // Lowercased kind -> (uri, kind as written, range) of its first block per file
+    let mut definitions: HashMap<String, Vec<(&Url, &str, Range)>> = HashMap::new();
+    for (uri, file) in files {
+        for block in &file.blocks {
+            let entries = definitions.entry(block.kind.to_lowercase()).or_default();
+            if !entries.iter().any(|(other, _, _)| other == uri) {
+                entries.push((*uri, block.kind.as_str(), block.range));
+            }
+        }
+    }
+
+    let mut result: HashMap<Url, Vec<Diagnostic>> = HashMap::new();
+    for entries in definitions.values().filter(|entries| entries.len() > 1) {
+        for (uri, kind, range) in entries {
+            let others: Vec<_> = entries
+                .iter()
+                .filter(|(other, _, _)| other != uri)
+                .collect();
+            result.entry((*uri).clone()).or_default().push(Diagnostic {
+                range: *range,
+                severity: Some(DiagnosticSeverity::ERROR),
+                message: format!(
+                    "Block '{}' is also defined in {}.",
+                    kind,
+                    others
+                        .iter()
+                        .map(|(other, _, _)| file_name(other))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                related_information: Some(
+                    others
+                        .iter()
+                        .map(|(other, _, other_range)| DiagnosticRelatedInformation {
+                            location: Location {
+                                uri: (*other).clone(),
+                                range: *other_range,
+                            },
+                            message: "Also defined here".to_string(),
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            });
+        }
+    }
+
+    for diagnostics in result.values_mut() {
+        diagnostics.sort_by_key(|d| (d.range.start.line, d.range.start.character));
+    }
+    result
+}
+
+/// The last path segment of `uri`, or the whole URI if it has none.
+fn file_name(uri: &Url) -> &str {
+    uri.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(uri.as_str())
+}
+
+fn result_id(text: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
@@ -0,0 +1,11 @@
+use tower_lsp::lsp_types::Range;
+
+use crate::ast::AmaroFile;
+
+/// Returns the ranges of the Rust code inside each top-level `{{ ... }}` block.
+///
+/// Clients can map these onto virtual Rust documents and hand them to
+/// rust-analyzer.
+pub fn rust_regions(file: &AmaroFile) -> Vec<Range> {
+    file.rust_blocks.iter().map(|block| block.range).collect()
+}
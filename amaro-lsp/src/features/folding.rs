@@ -0,0 +1,19 @@
+use tower_lsp::lsp_types::FoldingRange;
+
+use crate::ast::AmaroFile;
+
+/// Returns one folding range per block that spans more than one line.
+pub fn folding_ranges(file: &AmaroFile) -> Vec<FoldingRange> {
+    file.blocks
+        .iter()
+        .filter(|block| block.outer_range.end.line > block.outer_range.start.line)
+        .map(|block| FoldingRange {
+            start_line: block.outer_range.start.line,
+            start_character: None,
+            end_line: block.outer_range.end.line,
+            end_character: None,
+            kind: None,
+            collapsed_text: None,
+        })
+        .collect()
+}
@@ -0,0 +1,188 @@
+use tower_lsp::lsp_types::{Position, Range, TextEdit};
+
+use crate::ast::*;
+use crate::parser::closing_bracket;
+use crate::parser::utils::{calc_range, position_to_byte};
+
+const INDENT: usize = 4;
+
+/// Formats the whole document, returning a single edit or nothing if the
+/// text is already formatted.
+pub fn format_document(text: &str, file: &AmaroFile) -> Vec<TextEdit> {
+    let formatted = format_text(text, file);
+    if formatted == text {
+        return Vec::new();
+    }
+
+    vec![TextEdit {
+        range: Range {
+            start: Position::default(),
+            end: calc_range(text, text.len(), 0).end,
+        },
+        new_text: formatted,
+    }]
+}
+
+/// Re-emits the source with normalized layout.
+///
+/// - Block headers start at column 0.
+/// - Every item of a colon block is indented by four spaces; continuation
+///   lines keep their indentation relative to the item's first line.
+/// - Blank lines between blocks are collapsed to exactly one.
+/// - Trailing whitespace is removed from every line.
+pub fn format_text(text: &str, file: &AmaroFile) -> String {
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let lines: Vec<&str> = text.lines().map(|l| l.trim_end()).collect();
+
+    // Every line starts out as top-level gap content.
+    let mut layout: Vec<LineKind> = vec![LineKind::Gap; lines.len()];
+    for block in &file.blocks {
+        mark_block(&mut layout, &lines, text, block);
+    }
+
+    let mut out: Vec<String> = Vec::new();
+    let mut in_rust = false;
+    for (line, kind) in lines.iter().zip(&layout) {
+        match kind {
+            LineKind::Header => {
+                // Exactly one blank line between a block and what precedes it.
+                if !out.is_empty() && out.last().is_some_and(|l| !is_comment(l)) {
+                    trim_trailing_blank(&mut out);
+                    out.push(String::new());
+                }
+                out.push(line.trim_start().to_string());
+            }
+            LineKind::Shifted(delta) => {
+                if line.is_empty() {
+                    out.push(String::new());
+                } else {
+                    let indent = (indent_width(line) as isize + delta).max(INDENT as isize);
+                    out.push(format!(
+                        "{}{}",
+                        " ".repeat(indent as usize),
+                        line.trim_start()
+                    ));
+                }
+            }
+            LineKind::Verbatim => out.push(line.to_string()),
+            LineKind::Gap => {
+                let trimmed = line.trim_start();
+                if trimmed.starts_with("{{") {
+                    in_rust = true;
+                }
+
+                // Collapse runs of blank lines outside embedded Rust.
+                let collapse =
+                    !in_rust && line.is_empty() && out.last().is_some_and(|l| l.is_empty());
+                if !(collapse || line.is_empty() && out.is_empty()) {
+                    out.push(line.to_string());
+                }
+
+                if in_rust && trimmed.ends_with("}}") {
+                    in_rust = false;
+                }
+            }
+        }
+    }
+
+    trim_trailing_blank(&mut out);
+    let mut result = out.join(newline);
+    if !result.is_empty() {
+        result.push_str(newline);
+    }
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LineKind {
+    Gap,
+    Header,
+    Shifted(isize),
+    Verbatim,
+}
+
+fn mark_block(layout: &mut [LineKind], lines: &[&str], text: &str, block: &Block) {
+    let header = block.range.start.line as usize;
+    if header >= layout.len() {
+        return;
+    }
+    layout[header] = LineKind::Header;
+
+    // The header may follow another block on the same line, so start from
+    // the kind's own offset rather than the start of the line.
+    let after_kind = (position_to_byte(text, block.range.start) + block.kind.len()).min(text.len());
+    let rest = text[after_kind..].trim_start();
+
+    // Bracket blocks keep their interior untouched.
+    if rest.starts_with('[') {
+        let body = text.len() - rest.len() + 1;
+        let end = bracket_block_end_line(text, body).min(layout.len() - 1);
+        for kind in layout.iter_mut().take(end + 1).skip(header + 1) {
+            *kind = LineKind::Verbatim;
+        }
+        return;
+    }
+
+    let BlockContent::Fields(items) = &block.content;
+    let mut starts: Vec<(usize, usize)> = items
+        .iter()
+        .map(|item| match item {
+            BlockItem::Field(f) => (
+                f.key_range.start.line as usize,
+                f.value_range.end.line as usize,
+            ),
+            BlockItem::StructDef(s) => (s.range.start.line as usize, s.range.end.line as usize),
+        })
+        .filter(|&(start, _)| start > header && start < layout.len())
+        .collect();
+    starts.sort();
+
+    if starts.is_empty() {
+        return;
+    }
+    let end = starts
+        .iter()
+        .map(|&(_, end)| end)
+        .max()
+        .unwrap_or(header)
+        .min(layout.len() - 1);
+
+    // Lines before the first item (e.g. comments) are indented like fields.
+    for i in header + 1..starts[0].0 {
+        layout[i] = LineKind::Shifted(INDENT as isize - indent_width(lines[i]) as isize);
+    }
+
+    for (idx, &(start, _)) in starts.iter().enumerate() {
+        let next = starts.get(idx + 1).map(|&(s, _)| s).unwrap_or(end + 1);
+        let delta = INDENT as isize - indent_width(lines[start]) as isize;
+        for kind in layout.iter_mut().take(next.min(end + 1)).skip(start) {
+            *kind = LineKind::Shifted(delta);
+        }
+    }
+}
+
+/// Finds the line holding the `]` that closes a bracket block whose body
+/// starts at byte `body`.
+fn bracket_block_end_line(text: &str, body: usize) -> usize {
+    let end = closing_bracket(&text[body..])
+        .map(|i| body + i)
+        .unwrap_or(text.len());
+    text[..end].matches('\n').count()
+}
+
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { INDENT } else { 1 })
+        .sum()
+}
+
+fn is_comment(line: &str) -> bool {
+    line.trim_start().starts_with("//")
+}
+
+fn trim_trailing_blank(out: &mut Vec<String>) {
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+}
@@ -0,0 +1,82 @@
+use tower_lsp::lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position};
+
+use crate::ast::AmaroFile;
+use crate::parser::navigation::name_at_position;
+use crate::parser::symbols::{SymbolTable, Type, join_types};
+
+/// Builds the hover card for the name under `position`.
+///
+/// Block keywords get a short description, symbols from the global
+/// `SymbolTable` get their signature and, for builtins, their description.
+/// Returns `None` when neither applies.
+pub fn hover_at(file: &AmaroFile, position: Position) -> Option<Hover> {
+    let (name, range) = name_at_position(file, position)?;
+
+    let mut sections = Vec::new();
+
+    let table = SymbolTable::new();
+    if let Some(ty) = table.lookup(name) {
+        sections.push(format!("```amaro\n{}\n```", format_signature(name, ty)));
+    }
+    if let Some(doc) = table.doc(name) {
+        sections.push(doc.to_string());
+    }
+
+    if let Some(description) = block_description(name) {
+        sections.push(format!("**{}** block: {}", name, description));
+    }
+
+    if sections.is_empty() {
+        return None;
+    }
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: sections.join("\n\n"),
+        }),
+        range: Some(range),
+    })
+}
+
+/// Renders a symbol as `name(A, B) -> R` for functions and `name : T` otherwise.
+pub fn format_signature(name: &str, ty: &Type) -> String {
+    match ty {
+        Type::Function {
+            params,
+            return_type,
+            variadic,
+        } => format!(
+            "{}({}) -> {}",
+            name,
+            format_params(params, *variadic),
+            return_type
+        ),
+        other => format!("{} : {}", name, other),
+    }
+}
+
+/// Renders a parameter list; variadic functions end in `...`.
+fn format_params(params: &[Type], variadic: bool) -> String {
+    let mut rendered = join_types(params);
+    if variadic {
+        rendered.push_str("...");
+    }
+    rendered
+}
+
+/// One-line descriptions of the known block kinds.
+fn block_description(kind: &str) -> Option<&'static str> {
+    let description = match kind {
+        "RouteInfo" => "declares which gates are routed and how each one is realized.",
+        "TransitionInfo" => "declares how transitions are generated, applied, and costed.",
+        "ArchInfo" => "describes the target architecture and its qubit locations.",
+        "StateInfo" => "describes the routing state and its per-step cost.",
+        "GateRealization" => "a concrete realization of a routed gate on the architecture.",
+        "Transition" => "a transition applied to the qubit mapping between steps.",
+        "Architecture" | "Arch" => "the target hardware architecture.",
+        "Step" => "a single step of the routing schedule.",
+        _ => return None,
+    };
+    Some(description)
+}
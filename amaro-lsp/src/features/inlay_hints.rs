@@ -0,0 +1,52 @@
+use tower_lsp::lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, Range};
+
+use crate::ast::{AmaroFile, BlockContent, BlockItem};
+use crate::parser::semantics::{file_globals, infer_expr_type};
+use crate::parser::symbols::{SymbolTable, Type};
+
+/// Shows the inferred type after each field value whose key lies in `range`.
+///
+/// Fields whose type can't be inferred get no hint.
+pub fn inlay_hints(file: &AmaroFile, range: Range) -> Vec<InlayHint> {
+    let globals = file_globals(file, &mut Vec::new());
+    let mut hints = Vec::new();
+
+    for block in &file.blocks {
+        if block.outer_range.end < range.start || block.outer_range.start > range.end {
+            continue;
+        }
+
+        let mut sym_table = SymbolTable::new();
+        for (name, ty) in &globals {
+            sym_table.bind(name.clone(), ty.clone());
+        }
+
+        let BlockContent::Fields(items) = &block.content;
+        for item in items {
+            let BlockItem::Field(field) = item else {
+                continue;
+            };
+            if field.key_range.start < range.start || field.key_range.start > range.end {
+                continue;
+            }
+
+            let ty = infer_expr_type(&field.value, &mut sym_table, &mut Vec::new());
+            if ty == Type::Unknown {
+                continue;
+            }
+
+            hints.push(InlayHint {
+                position: field.value.range.end,
+                label: InlayHintLabel::String(format!(": {}", ty)),
+                kind: Some(InlayHintKind::TYPE),
+                text_edits: None,
+                tooltip: None,
+                padding_left: Some(true),
+                padding_right: None,
+                data: None,
+            });
+        }
+    }
+
+    hints
+}
@@ -0,0 +1,13 @@
+pub mod code_actions;
+pub mod completion;
+pub mod diagnostics;
+pub mod embedded;
+pub mod folding;
+pub mod formatting;
+pub mod hover;
+pub mod inlay_hints;
+pub mod navigation;
+pub mod selection_range;
+pub mod semantic_tokens;
+pub mod signature_help;
+pub mod workspace_symbols;
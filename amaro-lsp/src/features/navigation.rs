@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use tower_lsp::lsp_types::{
+    DocumentHighlight, DocumentHighlightKind, Location, Position, Range, TextEdit, Url,
+    WorkspaceEdit,
+};
+
+use crate::ast::{AmaroFile, Expr, ExprKind};
+use crate::parser::is_keyword;
+use crate::parser::navigation::name_at_position;
+
+/// Resolves the identifier under `position` to the block it names.
+///
+/// Returns the first block whose kind matches the identifier exactly, or
+/// `None` when the identifier isn't a block name.
+pub fn definition_at(uri: &Url, file: &AmaroFile, position: Position) -> Option<Location> {
+    let (name, _) = name_at_position(file, position)?;
+
+    file.blocks
+        .iter()
+        .find(|block| block.kind == name)
+        .map(|block| Location {
+            uri: uri.clone(),
+            range: block.range,
+        })
+}
+
+/// Finds every field or `let` value that mentions the block named under `position`.
+///
+/// With `include_declaration`, the matching block headers come first.
+pub fn references_at(
+    uri: &Url,
+    file: &AmaroFile,
+    position: Position,
+    include_declaration: bool,
+) -> Vec<Location> {
+    let Some((name, _)) = name_at_position(file, position) else {
+        return Vec::new();
+    };
+
+    let mut ranges = Vec::new();
+    if include_declaration {
+        ranges.extend(
+            file.blocks
+                .iter()
+                .filter(|block| block.kind == name)
+                .map(|block| block.range),
+        );
+    }
+
+    for value in file.values() {
+        collect_identifier_ranges(value, name, &mut ranges);
+    }
+
+    ranges
+        .into_iter()
+        .map(|range| Location {
+            uri: uri.clone(),
+            range,
+        })
+        .collect()
+}
+
+/// Highlights every occurrence of the name under `position` in this file.
+///
+/// Block headers and `let` names are writes; mentions in field and `let`
+/// values are reads.
+pub fn document_highlights_at(file: &AmaroFile, position: Position) -> Vec<DocumentHighlight> {
+    let Some((name, _)) = name_at_position(file, position) else {
+        return Vec::new();
+    };
+
+    let mut writes: Vec<Range> = file
+        .blocks
+        .iter()
+        .filter(|block| block.kind == name)
+        .map(|block| block.range)
+        .collect();
+    writes.extend(
+        file.bindings
+            .iter()
+            .filter(|binding| binding.name == name)
+            .map(|binding| binding.name_range),
+    );
+
+    let mut reads = Vec::new();
+    for value in file.values() {
+        collect_identifier_ranges(value, name, &mut reads);
+        collect_let_name_ranges(value, name, &mut writes);
+    }
+
+    let highlight = |range, kind| DocumentHighlight {
+        range,
+        kind: Some(kind),
+    };
+    let mut highlights: Vec<DocumentHighlight> = writes
+        .into_iter()
+        .map(|range| highlight(range, DocumentHighlightKind::WRITE))
+        .chain(
+            reads
+                .into_iter()
+                .map(|range| highlight(range, DocumentHighlightKind::READ)),
+        )
+        .collect();
+    highlights.sort_by_key(|h| (h.range.start.line, h.range.start.character));
+    highlights
+}
+
+/// Returns the range of the block name under `position`, if there is one.
+pub fn prepare_rename_at(file: &AmaroFile, position: Position) -> Option<Range> {
+    let (name, range) = name_at_position(file, position)?;
+
+    file.blocks
+        .iter()
+        .any(|block| block.kind == name)
+        .then_some(range)
+}
+
+/// Renames the block under `position` and every expression that mentions it.
+///
+/// Fails if the cursor isn't on a block name or if `new_name` is not an
+/// identifier, is a keyword, or is already used by another block.
+pub fn rename_at(
+    uri: &Url,
+    file: &AmaroFile,
+    position: Position,
+    new_name: &str,
+) -> Result<WorkspaceEdit, String> {
+    let Some((old_name, _)) = name_at_position(file, position)
+        .filter(|(name, _)| file.blocks.iter().any(|block| block.kind == *name))
+    else {
+        return Err("Only block names can be renamed.".to_string());
+    };
+
+    let is_identifier = new_name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && new_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_identifier {
+        return Err(format!("'{}' is not a valid block name.", new_name));
+    }
+    if is_keyword(new_name) {
+        return Err(format!("'{}' is a reserved keyword.", new_name));
+    }
+    if new_name != old_name && file.blocks.iter().any(|block| block.kind == new_name) {
+        return Err(format!("A block named '{}' already exists.", new_name));
+    }
+
+    let edits = references_at(uri, file, position, true)
+        .into_iter()
+        .map(|location| TextEdit {
+            range: location.range,
+            new_text: new_name.to_string(),
+        })
+        .collect();
+
+    Ok(WorkspaceEdit {
+        changes: Some(HashMap::from([(uri.clone(), edits)])),
+        ..Default::default()
+    })
+}
+
+fn collect_identifier_ranges(expr: &Expr, name: &str, out: &mut Vec<Range>) {
+    if let ExprKind::Identifier(ident) = &expr.kind
+        && ident == name
+    {
+        out.push(expr.range);
+    }
+    for child in expr.children() {
+        collect_identifier_ranges(child, name, out);
+    }
+}
+
+fn collect_let_name_ranges(expr: &Expr, name: &str, out: &mut Vec<Range>) {
+    if let ExprKind::LetBinding {
+        name: bound,
+        name_range,
+        ..
+    } = &expr.kind
+        && bound == name
+    {
+        out.push(*name_range);
+    }
+    for child in expr.children() {
+        collect_let_name_ranges(child, name, out);
+    }
+}
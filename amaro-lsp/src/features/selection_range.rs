@@ -0,0 +1,80 @@
+use tower_lsp::lsp_types::{Position, Range, SelectionRange};
+
+use crate::ast::{AmaroFile, BlockContent, BlockItem};
+use crate::parser::navigation::{block_at_position, nodes_at_position};
+
+/// Builds the expand-selection chain for each of `positions`.
+///
+/// Each chain runs from the innermost expression containing the position
+/// out through its enclosing expressions, the field or binding, and the
+/// block. Positions outside any block get an empty range.
+pub fn selection_ranges(file: &AmaroFile, positions: &[Position]) -> Vec<SelectionRange> {
+    positions
+        .iter()
+        .map(|&position| {
+            let mut chain = Vec::new();
+            collect_chain(file, position, &mut chain);
+            chain.dedup();
+
+            chain
+                .into_iter()
+                .fold(None, |parent, range| {
+                    Some(SelectionRange {
+                        range,
+                        parent: parent.map(Box::new),
+                    })
+                })
+                .unwrap_or(SelectionRange {
+                    range: Range::new(position, position),
+                    parent: None,
+                })
+        })
+        .collect()
+}
+
+/// Pushes the ranges containing `position`, outermost first.
+fn collect_chain(file: &AmaroFile, position: Position, chain: &mut Vec<Range>) {
+    let expr_ranges = nodes_at_position(file, position)
+        .into_iter()
+        .map(|expr| expr.range);
+
+    for binding in &file.bindings {
+        if contains(binding.range, position) {
+            chain.push(binding.range);
+            chain.extend(expr_ranges);
+            return;
+        }
+    }
+
+    let Some(block) = block_at_position(file, position) else {
+        return;
+    };
+    chain.push(block.outer_range);
+
+    let BlockContent::Fields(items) = &block.content;
+    for item in items {
+        match item {
+            BlockItem::Field(field) => {
+                let range = Range::new(field.key_range.start, field.value_range.end);
+                if contains(range, position) {
+                    chain.push(range);
+                    chain.extend(expr_ranges);
+                    return;
+                }
+            }
+            BlockItem::StructDef(def) => {
+                if contains(def.range, position) {
+                    chain.push(def.range);
+                    if let Some(param) = def.fields.iter().find(|p| contains(p.range, position)) {
+                        chain.push(param.range);
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn contains(range: Range, position: Position) -> bool {
+    range.start <= position && position <= range.end
+}
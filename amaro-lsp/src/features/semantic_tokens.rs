@@ -0,0 +1,183 @@
+use tower_lsp::lsp_types::{SemanticToken, SemanticTokenType, SemanticTokensLegend};
+
+use crate::ast::AmaroFile;
+use crate::parser::symbols::{SymbolTable, Type};
+use crate::parser::utils::position_to_byte;
+use crate::parser::{is_keyword, parse_rust_embedded_robust};
+
+/// Token types reported by the server; a token's `token_type` indexes this list.
+pub const TOKEN_TYPES: [SemanticTokenType; 7] = [
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::OPERATOR,
+    SemanticTokenType::MACRO,
+];
+
+const KEYWORD: u32 = 0;
+const TYPE: u32 = 1;
+const FUNCTION: u32 = 2;
+const STRING: u32 = 3;
+const NUMBER: u32 = 4;
+const OPERATOR: u32 = 5;
+const MACRO: u32 = 6;
+
+/// Operators, longest first so `->` wins over `-`.
+const OPERATORS: [&str; 18] = [
+    "**", "==", "!=", "<=", ">=", "&&", "||", "..", "->", "+", "-", "*", "/", "%", "<", ">", "!",
+    "⊗",
+];
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: Vec::new(),
+    }
+}
+
+/// Classifies the tokens of a document and returns them delta-encoded.
+///
+/// Block kinds come from the parsed `file`; other names are looked up in
+/// the global `SymbolTable` (gate literals are types, builtins are
+/// functions). Embedded `{{ ... }}` Rust is reported as one `macro` token per
+/// line so it isn't highlighted as Amaro.
+pub fn semantic_tokens(text: &str, file: &AmaroFile) -> Vec<SemanticToken> {
+    let table = SymbolTable::new();
+    let block_starts: Vec<usize> = file
+        .blocks
+        .iter()
+        .map(|b| position_to_byte(text, b.range.start))
+        .collect();
+
+    // (byte offset, byte length, token type); never spans a line break.
+    let mut raw: Vec<(usize, usize, u32)> = Vec::new();
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+        let c = rest.chars().next().unwrap_or_default();
+
+        if rest.starts_with("//") {
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if rest.starts_with("{{") {
+            let len = match parse_rust_embedded_robust(rest) {
+                Ok((after, _)) => rest.len() - after.len(),
+                Err(_) => rest.len(),
+            };
+            let mut line_start = i;
+            for line in text[i..i + len].split_inclusive('\n') {
+                let content = line.trim_end_matches(['\n', '\r']);
+                if !content.is_empty() {
+                    raw.push((line_start, content.len(), MACRO));
+                }
+                line_start += line.len();
+            }
+            i += len;
+        } else if c == '\'' || c == '"' {
+            let len = string_len(rest, c);
+            raw.push((i, len, STRING));
+            i += len;
+        } else if c.is_ascii_digit() {
+            let len = number_len(rest);
+            raw.push((i, len, NUMBER));
+            i += len;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+                .unwrap_or(rest.len());
+            let name = &rest[..len];
+
+            let token_type = if is_keyword(name) {
+                Some(KEYWORD)
+            } else if block_starts.contains(&i) {
+                Some(TYPE)
+            } else {
+                match table.lookup(name) {
+                    Some(Type::Gate) => Some(TYPE),
+                    Some(Type::Function { .. }) => Some(FUNCTION),
+                    _ => None,
+                }
+            };
+            if let Some(token_type) = token_type {
+                raw.push((i, len, token_type));
+            }
+            i += len;
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            raw.push((i, op.len(), OPERATOR));
+            i += op.len();
+        } else {
+            i += c.len_utf8().max(1);
+        }
+    }
+
+    encode(text, &raw)
+}
+
+/// Length of a quoted string starting at `rest`, stopping at the end of the
+/// line if it is unterminated.
+fn string_len(rest: &str, quote: char) -> usize {
+    let mut escape = false;
+    for (j, ch) in rest.char_indices().skip(1) {
+        match ch {
+            '\n' | '\r' => return j,
+            _ if escape => escape = false,
+            '\\' => escape = true,
+            _ if ch == quote => return j + 1,
+            _ => {}
+        }
+    }
+    rest.len()
+}
+
+/// Length of a numeric literal, leaving a following `..` range operator alone.
+fn number_len(rest: &str) -> usize {
+    let mut len = 0;
+    for (j, ch) in rest.char_indices() {
+        let continues = ch.is_ascii_alphanumeric()
+            || ch == '_'
+            || (ch == '.' && !rest[j + 1..].starts_with('.'));
+        if !continues {
+            break;
+        }
+        len = j + 1;
+    }
+    len
+}
+
+/// Delta-encodes `raw`, which must be sorted by offset.
+fn encode(text: &str, raw: &[(usize, usize, u32)]) -> Vec<SemanticToken> {
+    let utf16_len = |s: &str| s.chars().map(|c| c.len_utf16() as u32).sum::<u32>();
+
+    let mut tokens = Vec::with_capacity(raw.len());
+    let (mut line, mut line_start, mut cursor) = (0u32, 0usize, 0usize);
+    let (mut prev_line, mut prev_start) = (0u32, 0u32);
+
+    for &(offset, len, token_type) in raw {
+        for (j, _) in text[cursor..offset].match_indices('\n') {
+            line += 1;
+            line_start = cursor + j + 1;
+        }
+        cursor = offset;
+
+        let start = utf16_len(&text[line_start..offset]);
+        let delta_line = line - prev_line;
+        tokens.push(SemanticToken {
+            delta_line,
+            delta_start: if delta_line == 0 {
+                start - prev_start
+            } else {
+                start
+            },
+            length: utf16_len(&text[offset..offset + len]),
+            token_type,
+            token_modifiers_bitset: 0,
+        });
+
+        prev_line = line;
+        prev_start = start;
+    }
+
+    tokens
+}
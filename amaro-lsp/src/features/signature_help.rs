@@ -0,0 +1,109 @@
+use tower_lsp::lsp_types::{
+    ParameterInformation, ParameterLabel, Position, SignatureHelp, SignatureInformation,
+};
+
+use super::hover::format_signature;
+use crate::ast::{AmaroFile, ExprKind};
+use crate::parser::navigation::nodes_at_position;
+use crate::parser::symbols::{SymbolTable, Type};
+use crate::parser::utils::{identifier_at, position_to_byte};
+
+/// Builds signature help for the builtin call enclosing `position`.
+///
+/// The active parameter is the number of arguments before the cursor. A
+/// call that is still being typed doesn't parse, so when `file` has no call
+/// there the text is scanned back to the unclosed `(` instead. Returns
+/// `None` outside a call or when the callee isn't a known function.
+pub fn signature_help_at(
+    text: &str,
+    file: Option<&AmaroFile>,
+    position: Position,
+) -> Option<SignatureHelp> {
+    let (name, commas) = file
+        .and_then(|file| parsed_call(file, position))
+        .or_else(|| unparsed_call(text, position))?;
+
+    let table = SymbolTable::new();
+    let ty = table.lookup(&name)?;
+    let Type::Function {
+        params, variadic, ..
+    } = ty
+    else {
+        return None;
+    };
+
+    let active = if *variadic {
+        commas.min(params.len().saturating_sub(1))
+    } else {
+        commas
+    };
+
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label: format_signature(&name, ty),
+            documentation: None,
+            parameters: Some(
+                params
+                    .iter()
+                    .map(|param| ParameterInformation {
+                        label: ParameterLabel::Simple(param.to_string()),
+                        documentation: None,
+                    })
+                    .collect(),
+            ),
+            active_parameter: Some(active as u32),
+        }],
+        active_signature: Some(0),
+        active_parameter: Some(active as u32),
+    })
+}
+
+/// The callee and active argument of the innermost parsed call whose
+/// argument list contains `position`.
+fn parsed_call(file: &AmaroFile, position: Position) -> Option<(String, usize)> {
+    nodes_at_position(file, position)
+        .into_iter()
+        .rev()
+        .find_map(|node| match &node.kind {
+            ExprKind::FunctionCall { function, args } if function.range.end < position => {
+                let ExprKind::Identifier(name) = &function.kind else {
+                    return None;
+                };
+                let before = args.iter().filter(|arg| arg.range.end < position).count();
+                Some((name.clone(), before))
+            }
+            _ => None,
+        })
+}
+
+/// The callee and number of top-level commas before `position` for the
+/// innermost unclosed `(` in `text`.
+fn unparsed_call(text: &str, position: Position) -> Option<(String, usize)> {
+    let offset = position_to_byte(text, position);
+    let (open_paren, commas) = enclosing_call(&text[..offset])?;
+
+    let (start, end) = identifier_at(text, open_paren)?;
+    (end == open_paren).then(|| (text[start..end].to_string(), commas))
+}
+
+/// Walks back from the end of `before` to the innermost unclosed `(`.
+///
+/// Returns its byte offset and the number of top-level commas after it, or
+/// `None` if an unclosed `[` or `{` is reached first.
+fn enclosing_call(before: &str) -> Option<(usize, usize)> {
+    let mut depth = 0usize;
+    let mut commas = 0;
+
+    for (i, c) in before.char_indices().rev() {
+        match c {
+            ')' | ']' | '}' => depth += 1,
+            '(' if depth == 0 => return Some((i, commas)),
+            '[' | '{' if depth == 0 => return None,
+            '(' | '[' | '{' => depth -= 1,
+            ',' if depth == 0 => commas += 1,
+            _ => {}
+        }
+    }
+
+    None
+}
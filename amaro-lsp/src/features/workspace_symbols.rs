@@ -0,0 +1,56 @@
+use tower_lsp::lsp_types::{Location, SymbolInformation, SymbolKind, Url};
+
+use crate::ast::AmaroFile;
+
+/// The outline kind for a block of kind `kind`.
+pub fn block_symbol_kind(kind: &str) -> SymbolKind {
+    match kind {
+        "GateRealization" | "Transition" | "Architecture" | "Arch" => SymbolKind::CLASS,
+        "Step" => SymbolKind::FUNCTION,
+        "RouteInfo" | "TransitionInfo" | "ArchInfo" | "StateInfo" => SymbolKind::MODULE,
+        _ => SymbolKind::OBJECT,
+    }
+}
+
+/// Finds the blocks across `files` whose kind matches `query`.
+///
+/// A kind matches when the query's characters appear in it in order,
+/// ignoring case, so `trin` finds `TransitionInfo`. An empty query matches
+/// every block. Results are sorted by kind, then by document.
+pub fn workspace_symbols(files: &[(&Url, &AmaroFile)], query: &str) -> Vec<SymbolInformation> {
+    let mut symbols: Vec<SymbolInformation> = files
+        .iter()
+        .flat_map(|(uri, file)| file.blocks.iter().map(move |block| (*uri, block)))
+        .filter(|(_, block)| fuzzy_matches(query, &block.kind))
+        .map(|(uri, block)| {
+            #[allow(deprecated)]
+            SymbolInformation {
+                name: block.kind.clone(),
+                kind: block_symbol_kind(&block.kind),
+                tags: None,
+                deprecated: None,
+                location: Location::new(uri.clone(), block.range),
+                container_name: None,
+            }
+        })
+        .collect();
+
+    symbols.sort_by(|a, b| {
+        (&a.name, a.location.uri.as_str(), a.location.range.start).cmp(&(
+            &b.name,
+            b.location.uri.as_str(),
+            b.location.range.start,
+        ))
+    });
+    symbols
+}
+
+/// Returns true if the characters of `query` appear in `name` in order,
+/// ignoring case.
+fn fuzzy_matches(query: &str, name: &str) -> bool {
+    let mut name_chars = name.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|q| name_chars.any(|c| c == q))
+}
@@ -1,6 +1,7 @@
 pub mod ast;
+pub mod features;
 pub mod parser;
 pub mod server;
 
 pub use ast::*;
-pub use parser::parse_file;
+pub use parser::{diagnose, parse_file};
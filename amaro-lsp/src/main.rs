@@ -1,8 +1,4 @@
-mod ast;
-mod parser;
-mod server;
-
-use server::Backend;
+use amaro_lsp::server::Backend;
 use tower_lsp::{LspService, Server};
 
 #[tokio::main]
@@ -11,6 +7,8 @@ async fn main() {
     let stdout = tokio::io::stdout();
 
     // let (service, socket) = LspService::new(|client| Backend::new(client));
-    let (service, socket) = LspService::new(Backend::new);
+    let (service, socket) = LspService::build(Backend::new)
+        .custom_method("amaro/rustRegions", Backend::rust_regions)
+        .finish();
     Server::new(stdin, stdout, socket).serve(service).await;
 }
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Url};
+
+use super::semantics::KNOWN_BLOCKS;
+
+pub const DEFAULT_MAX_FILE_BYTES: usize = 1024 * 1024;
+
+/// Settings that change how documents are parsed and checked.
+#[derive(Debug, Clone)]
+pub struct AnalysisConfig {
+    /// Block kinds the parser and semantic checks accept.
+    pub known_blocks: Vec<String>,
+    /// Files larger than this are parsed as an outline of block headers and
+    /// get block-level checks only.
+    pub max_file_bytes: usize,
+    /// Overrides for the severity of the rules in [`CONFIGURABLE_RULES`],
+    /// keyed by rule name.
+    pub severities: HashMap<String, RuleSeverity>,
+    /// Whether files must define the mandatory blocks. Off for library
+    /// fragments that only define part of a program.
+    pub require_mandatory_blocks: bool,
+    /// Files whose name matches this glob (`*` and `?` wildcards) are
+    /// treated as fragments and skip the mandatory-block check.
+    pub fragment_glob: Option<String>,
+    /// The document being checked, for diagnostics that link to another
+    /// place in it. Set by [`AnalysisConfig::for_document`].
+    pub document_uri: Option<Url>,
+}
+
+/// Rules whose severity can be configured. Each name is also the `code` of
+/// the diagnostics the rule emits.
+pub const CONFIGURABLE_RULES: [&str; 12] = [
+    "capitalization",
+    "unknownBlock",
+    "emptyBlock",
+    "nonstandardGate",
+    "unusedLet",
+    "branchTypes",
+    "divisionByZero",
+    "emptyRange",
+    "applyValue",
+    "unreachableBranch",
+    "chainedComparison",
+    "cyclicReference",
+];
+
+/// A configured severity for a rule; `Off` drops its diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSeverity {
+    Error,
+    Warning,
+    #[serde(alias = "info")]
+    Information,
+    Hint,
+    Off,
+}
+
+/// The diagnostic `code` for a configurable rule.
+pub fn rule_code(rule: &str) -> Option<NumberOrString> {
+    debug_assert!(CONFIGURABLE_RULES.contains(&rule), "unknown rule {}", rule);
+    Some(NumberOrString::String(rule.to_string()))
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        AnalysisConfig {
+            known_blocks: KNOWN_BLOCKS.iter().map(|kb| kb.to_string()).collect(),
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            severities: HashMap::new(),
+            require_mandatory_blocks: true,
+            fragment_glob: None,
+            document_uri: None,
+        }
+    }
+}
+
+impl AnalysisConfig {
+    /// Returns the correctly capitalized known block kind matching `kind`, if any.
+    pub fn canonical_block_name(&self, kind: &str) -> Option<&str> {
+        self.known_blocks
+            .iter()
+            .find(|kb| kb.eq_ignore_ascii_case(kind))
+            .map(String::as_str)
+    }
+
+    /// The configuration for the file at `uri`: fragments matching
+    /// `fragment_glob` don't require the mandatory blocks.
+    pub fn for_document(&self, uri: &Url) -> AnalysisConfig {
+        let mut config = self.clone();
+        config.document_uri = Some(uri.clone());
+        let file_name = uri.path().rsplit('/').next().unwrap_or_default();
+        if let Some(glob) = &self.fragment_glob
+            && glob_matches(glob, file_name)
+        {
+            config.require_mandatory_blocks = false;
+        }
+        config
+    }
+
+    /// Applies the configured rule severities, dropping diagnostics of rules
+    /// that are turned off.
+    pub fn apply_severities(&self, diagnostics: &mut Vec<Diagnostic>) {
+        if self.severities.is_empty() {
+            return;
+        }
+        diagnostics.retain_mut(|diagnostic| {
+            let Some(NumberOrString::String(rule)) = &diagnostic.code else {
+                return true;
+            };
+            let severity = match self.severities.get(rule) {
+                None => return true,
+                Some(RuleSeverity::Off) => return false,
+                Some(RuleSeverity::Error) => DiagnosticSeverity::ERROR,
+                Some(RuleSeverity::Warning) => DiagnosticSeverity::WARNING,
+                Some(RuleSeverity::Information) => DiagnosticSeverity::INFORMATION,
+                Some(RuleSeverity::Hint) => DiagnosticSeverity::HINT,
+            };
+            diagnostic.severity = Some(severity);
+            true
+        });
+    }
+}
+
+/// Matches `name` against a glob where `*` is any run of characters and
+/// `?` is any single character.
+fn glob_matches(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut g, mut n) = (0, 0);
+    // Where to resume after the last `*` if the current attempt fails
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    g = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
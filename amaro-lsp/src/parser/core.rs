@@ -3,16 +3,18 @@ use nom::{
     branch::alt,
     bytes::complete::{tag, take_while},
     character::complete::{char, multispace1, not_line_ending, satisfy},
-    combinator::{map, peek, recognize, verify},
+    combinator::{map, opt, peek, recognize, verify},
     multi::{many0, separated_list0, separated_list1},
     sequence::{delimited, pair},
 };
 
 use nom::error::Error;
 
+use super::config::AnalysisConfig;
 use super::expr::parse_expr;
 use super::utils::calc_range;
 use crate::ast::*;
+use tower_lsp::lsp_types::Range;
 
 // Whitespaces and Comments
 pub fn whitespace_handler(input: &str) -> IResult<&str, &str> {
@@ -30,22 +32,40 @@ pub fn parse_rust_embedded_robust(input: &str) -> IResult<&str, &str> {
 
     let mut depth = 1;
     let mut in_string = false;
-    let mut in_char = false;
     let mut escape = false;
     let mut bytes_consumed = 0;
+    let mut chars = input.char_indices().peekable();
 
-    for (i, ch) in input.char_indices() {
+    while let Some((i, ch)) = chars.next() {
         if escape {
             escape = false;
             continue;
         }
 
         match ch {
-            '\\' if in_string || in_char => escape = true,
-            '"' if !in_char => in_string = !in_string,
-            '\'' if !in_string => in_char = !in_char,
-            '{' if !in_string && !in_char => depth += 1,
-            '}' if !in_string && !in_char => {
+            '\\' if in_string => escape = true,
+            '"' => in_string = !in_string,
+            // Char literals ('x', '\n', '{') are skipped whole; a quote that
+            // doesn't close one is a lifetime ('a) and is ignored.
+            '\'' if !in_string => {
+                let rest = &input[i + 1..];
+                // Skip the escaped char so '\'' closes on the right quote.
+                let literal_len = if let Some(escaped) = rest.strip_prefix('\\') {
+                    let skip = 1 + escaped.chars().next().map_or(0, char::len_utf8);
+                    rest[skip..].find('\'').map(|end| skip + end + 1)
+                } else {
+                    let mut next = rest.chars();
+                    match (next.next(), next.next()) {
+                        (Some(c), Some('\'')) => Some(c.len_utf8() + 1),
+                        _ => None,
+                    }
+                };
+                if let Some(len) = literal_len {
+                    while chars.next_if(|&(j, _)| j <= i + len).is_some() {}
+                }
+            }
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => {
                 depth -= 1;
                 if depth == 0 {
                     bytes_consumed = i;
@@ -89,6 +109,7 @@ pub fn is_keyword(s: &str) -> bool {
         s,
         "if" | "then"
             | "else"
+            | "elif"
             | "let"
             | "in"
             | "true"
@@ -100,12 +121,22 @@ pub fn is_keyword(s: &str) -> bool {
     )
 }
 
+/// A backtick-escaped identifier such as `` `then` ``, letting a keyword be
+/// used as a name. Returns the name without the backticks.
+pub fn parse_raw_identifier(input: &str) -> IResult<&str, &str> {
+    delimited(char('`'), parse_identifier, char('`'))(input)
+}
+
+/// An identifier that is not a keyword, unless escaped with backticks.
 pub fn parse_non_keyword_identifier(input: &str) -> IResult<&str, &str> {
-    verify(parse_identifier, |s: &str| !is_keyword(s))(input)
+    alt((
+        parse_raw_identifier,
+        verify(parse_identifier, |s: &str| !is_keyword(s)),
+    ))(input)
 }
 
 // Type Annotations
-fn parse_type_annotation(input: &str) -> IResult<&str, TypeAnnotation> {
+pub fn parse_type_annotation(input: &str) -> IResult<&str, TypeAnnotation> {
     alt((parse_function_type, parse_atomic_type))(input)
 }
 
@@ -180,24 +211,29 @@ fn parse_struct_def<'a>(original_input: &'a str, input: &'a str) -> IResult<&'a
 }
 
 // Field & Block Parsing
-fn parse_field<'a>(original_input: &'a str, input: &'a str) -> IResult<&'a str, Field> {
+fn parse_field<'a>(
+    original_input: &'a str,
+    input: &'a str,
+    errors: &mut Vec<SyntaxError>,
+) -> IResult<&'a str, Field> {
     let (input, _) = whitespace_handler(input)?;
 
     let key_start = input.as_ptr() as usize - original_input.as_ptr() as usize;
-    let (input, key) = parse_non_keyword_identifier(input)?;
-    let key_len = key.len();
+    let (rest, key) = parse_non_keyword_identifier(input)?;
+    let key_len = input.len() - rest.len();
+    let input = rest;
 
     let (input, _) = ws(char('='))(input)?;
 
     let val_start = input.as_ptr() as usize - original_input.as_ptr() as usize;
-    let (input, first_expr) = parse_expr(original_input, input)?;
+    let (input, first_expr) = parse_expr(original_input, input, errors)?;
 
     // Check for comma-separated list (e.g., routed_gates = CX, T)
     let (input, rest_exprs) = many0(|i: &'a str| {
         let (i, _) = whitespace_handler(i)?;
         let (i, _) = char(',')(i)?;
         let (i, _) = whitespace_handler(i)?;
-        parse_expr(original_input, i)
+        parse_expr(original_input, i, errors)
     })(input)?;
 
     // If there were commas, wrap everything into a List. Else just return the single expression.
@@ -229,6 +265,7 @@ fn parse_field<'a>(original_input: &'a str, input: &'a str) -> IResult<&'a str,
 fn parse_block_item<'a>(
     original_input: &'a str,
     input: &'a str,
+    errors: &mut Vec<SyntaxError>,
 ) -> IResult<&'a str, Option<BlockItem>> {
     let (input, _) = whitespace_handler(input)?;
 
@@ -236,60 +273,178 @@ fn parse_block_item<'a>(
         return Ok((input, Some(BlockItem::StructDef(struct_def))));
     }
 
-    if let Ok((input, field)) = parse_field(original_input, input) {
+    if let Ok((input, field)) = parse_field(original_input, input, errors) {
         return Ok((input, Some(BlockItem::Field(field))));
     }
 
     Ok((input, None))
 }
 
-fn extract_block_items(original_input: &str, body_text: &str) -> Vec<BlockItem> {
+fn extract_block_items(
+    original_input: &str,
+    body_text: &str,
+    errors: &mut Vec<SyntaxError>,
+) -> Vec<BlockItem> {
     let mut items = Vec::new();
     let mut current_input = body_text;
 
     while !current_input.trim().is_empty() {
-        match parse_block_item(original_input, current_input) {
+        match parse_block_item(original_input, current_input, errors) {
             Ok((rest, Some(item))) => {
                 items.push(item);
                 current_input = rest;
             }
-            Ok((_rest, None)) => {
-                if let Some(pos) = current_input.find('\n') {
-                    current_input = &current_input[pos + 1..];
-                } else {
+            Ok((_, None)) | Err(_) => {
+                let rest = whitespace_handler(current_input).map_or(current_input, |(r, _)| r);
+                if rest.is_empty() {
                     break;
                 }
+
+                // Report the line that failed, then skip it along with any
+                // more-indented lines continuing it.
+                let line_end = rest.find('\n').unwrap_or(rest.len());
+                let line = rest[..line_end].trim_end();
+                let start = line.as_ptr() as usize - original_input.as_ptr() as usize;
+                let error = match unterminated_string_start(line) {
+                    Some(quote) => SyntaxError {
+                        message: "Unterminated string literal".to_string(),
+                        range: calc_range(original_input, start + quote, line.len() - quote),
+                    },
+                    None => SyntaxError {
+                        message: invalid_item_message(line),
+                        range: calc_range(original_input, start, line.len()),
+                    },
+                };
+                errors.push(error);
+
+                let indent = start - original_input[..start].rfind('\n').map_or(0, |i| i + 1);
+                current_input = skip_continuation_lines(&rest[line_end..], indent);
             }
-            Err(_) => {
-                if let Some(pos) = current_input.find('\n') {
-                    current_input = &current_input[pos + 1..];
-                } else {
-                    break;
+        }
+    }
+
+    items
+}
+
+/// Describes why a block body line could not be parsed.
+fn invalid_item_message(line: &str) -> String {
+    let field_key =
+        parse_non_keyword_identifier(line).and_then(|(rest, key)| ws(char('='))(rest).map(|_| key));
+    match field_key {
+        Ok(key) => format!("Invalid value for field '{}'.", key),
+        Err(_) => "Expected a field (`name = value`) or struct definition.".to_string(),
+    }
+}
+
+/// Skips blank lines and lines indented deeper than `indent`, starting at the
+/// line break that ends a failed line.
+fn skip_continuation_lines(input: &str, indent: usize) -> &str {
+    let mut current = input;
+    while let Some(next) = current.strip_prefix('\n') {
+        let line = &next[..next.find('\n').unwrap_or(next.len())];
+        let line_indent = line.len() - line.trim_start().len();
+        if !line.trim().is_empty() && line_indent <= indent {
+            return next;
+        }
+        current = &next[line.len()..];
+    }
+    current
+}
+
+/// Returns the offset of the `]` closing a bracket block whose body is
+/// `body`, ignoring brackets inside string literals and `//` comments.
+pub fn closing_bracket(body: &str) -> Option<usize> {
+    let mut depth = 1;
+    let mut quote: Option<char> = None;
+    let mut chars = body.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match quote {
+            Some(open) => {
+                if c == '\\' {
+                    chars.next();
+                } else if c == open || c == '\n' {
+                    // An unterminated string ends with its line
+                    quote = None;
                 }
             }
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '/' if chars.peek().is_some_and(|&(_, next)| next == '/') => {
+                    while chars.next_if(|&(_, next)| next != '\n').is_some() {}
+                }
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            },
         }
     }
+    None
+}
 
-    items
+/// Returns the offset of a string literal's opening quote if the string is
+/// not closed before the end of `line`.
+fn unterminated_string_start(line: &str) -> Option<usize> {
+    let mut open: Option<(usize, char)> = None;
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match open {
+            Some((_, quote)) => {
+                if c == '\\' {
+                    chars.next();
+                } else if c == quote {
+                    open = None;
+                }
+            }
+            None => match c {
+                '\'' | '"' => open = Some((i, c)),
+                '/' if chars.peek().is_some_and(|&(_, next)| next == '/') => break,
+                _ => {}
+            },
+        }
+    }
+
+    open.map(|(start, _)| start)
 }
 
-fn is_new_block_start(line: &str) -> bool {
+/// A line starts a new block when it reads `Kind:` or `Kind[`. Lines at
+/// column 0 may use any identifier. Indented lines must name a known kind
+/// exactly, and an indented `Kind:` must end the line, so body lines such as
+/// `edges[0]` or a struct parameter `step : Int` aren't mistaken for a header.
+fn is_new_block_start(line: &str, config: &AnalysisConfig) -> bool {
     let trimmed = line.trim_start();
-    match parse_identifier(trimmed) {
-        Ok((rest, _)) => {
-            let next_char = rest.trim_start().chars().next();
-            matches!(next_char, Some('[') | Some(':'))
-        }
-        Err(_) => false,
+    let Ok((rest, kind)) = parse_identifier(trimmed) else {
+        return false;
+    };
+    let rest = rest.trim_start_matches([' ', '\t']);
+
+    if trimmed.len() == line.len() {
+        return rest.starts_with(['[', ':']);
     }
+
+    let known = config.canonical_block_name(kind) == Some(kind);
+    let ends_line = |after: &str| {
+        let after = after.trim_start_matches([' ', '\t']);
+        after.is_empty() || after.starts_with(['\n', '\r']) || after.starts_with("//")
+    };
+    known && (rest.starts_with('[') || rest.strip_prefix(':').is_some_and(ends_line))
 }
 
-pub fn consume_remaining_block(input: &str) -> IResult<&str, &str> {
+pub fn consume_remaining_block<'a>(
+    input: &'a str,
+    config: &AnalysisConfig,
+) -> IResult<&'a str, &'a str> {
     let mut current = input;
     let mut len = 0;
 
     loop {
-        if current.is_empty() || is_new_block_start(current) {
+        if current.is_empty() || is_new_block_start(current, config) {
             break;
         }
         match not_line_ending::<&str, Error<&str>>(current) {
@@ -313,7 +468,13 @@ pub fn consume_remaining_block(input: &str) -> IResult<&str, &str> {
     Ok((current, &input[..len]))
 }
 
-pub fn parse_block<'a>(original_input: &'a str, input: &'a str) -> IResult<&'a str, Option<Block>> {
+/// Parses one block, recording recoverable syntax errors in `errors`.
+pub fn parse_block<'a>(
+    original_input: &'a str,
+    input: &'a str,
+    errors: &mut Vec<SyntaxError>,
+    config: &AnalysisConfig,
+) -> IResult<&'a str, Option<Block>> {
     let (input, _) = whitespace_handler(input)?;
     if input.is_empty() {
         return Ok((input, None));
@@ -329,52 +490,60 @@ pub fn parse_block<'a>(original_input: &'a str, input: &'a str) -> IResult<&'a s
 
     if check_colon.is_ok() {
         let (input, _) = char(':')(input)?;
-        let (input, body_content) = consume_remaining_block(input)?;
-        let items = extract_block_items(original_input, body_content);
+        let (input, body_content) = consume_remaining_block(input, config)?;
+        let items = if original_input.len() > config.max_file_bytes {
+            Vec::new()
+        } else {
+            extract_block_items(original_input, body_content, errors)
+        };
+
+        let body_start = body_content.as_ptr() as usize - original_input.as_ptr() as usize;
+        let end_offset = body_start + body_content.trim_end().len();
 
         return Ok((
             input,
             Some(Block::new(
                 kind.to_string(),
                 calc_range(original_input, start_offset, kind.len()),
+                calc_range(original_input, start_offset, end_offset - start_offset),
                 BlockContent::Fields(items),
             )),
         ));
     }
 
     if check_bracket.is_ok() {
+        let bracket_offset = input.as_ptr() as usize - original_input.as_ptr() as usize;
         let (input, _) = char('[')(input)?;
-        let body_start = input.as_ptr() as usize - original_input.as_ptr() as usize;
-
-        let bytes = &original_input.as_bytes()[body_start..];
-        let mut depth = 1;
-        let mut body_end = body_start;
+        let body_start = bracket_offset + 1;
 
-        for (i, &b) in bytes.iter().enumerate() {
-            match b {
-                b'[' => depth += 1,
-                b']' => {
-                    depth -= 1;
-                    if depth == 0 {
-                        body_end = body_start + i;
-                        break;
-                    }
-                }
-                _ => {}
+        // Without a closing bracket the body runs to the next block header
+        let (input, inner_body, end_offset) = match closing_bracket(input) {
+            Some(len) => {
+                let (rest, _) = char(']')(&input[len..])?;
+                (rest, &input[..len], body_start + len + 1)
             }
-        }
-
-        let inner_body = &original_input[body_start..body_end];
-        let items = extract_block_items(original_input, inner_body);
+            None => {
+                errors.push(SyntaxError {
+                    message: format!("Unclosed '[' for block '{}'", kind),
+                    range: calc_range(original_input, bracket_offset, 1),
+                });
+                let (rest, body) = consume_remaining_block(input, config)?;
+                (rest, body, body_start + body.trim_end().len())
+            }
+        };
 
-        let remaining_input = &original_input[body_end..];
-        let (input, _) = char(']')(remaining_input)?;
+        let items = if original_input.len() > config.max_file_bytes {
+            Vec::new()
+        } else {
+            extract_block_items(original_input, inner_body, errors)
+        };
 
         return Ok((
             input,
             Some(Block::new(
                 kind.to_string(),
                 calc_range(original_input, start_offset, kind.len()),
+                calc_range(original_input, start_offset, end_offset - start_offset),
                 BlockContent::Fields(items),
             )),
         ));
@@ -383,6 +552,40 @@ pub fn parse_block<'a>(original_input: &'a str, input: &'a str) -> IResult<&'a s
     Ok((input, None))
 }
 
+/// Parses a top-level `let name = expr` binding (optionally ending in `;`).
+fn parse_top_level_let<'a>(
+    original_input: &'a str,
+    input: &'a str,
+    errors: &mut Vec<SyntaxError>,
+) -> IResult<&'a str, LetDef> {
+    let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
+
+    let (input, _) = tag("let")(input)?;
+    let (input, _) = multispace1(input)?;
+
+    let name_start = input.as_ptr() as usize - original_input.as_ptr() as usize;
+    let (rest, name) = parse_non_keyword_identifier(input)?;
+    let name_len = input.len() - rest.len();
+    let (input, _) = ws(char('='))(rest)?;
+    let (input, value) = parse_expr(original_input, input, errors)?;
+    let (input, _) = opt(ws(char(';')))(input)?;
+
+    let range = Range {
+        start: calc_range(original_input, start, 0).start,
+        end: value.range.end,
+    };
+
+    Ok((
+        input,
+        LetDef::new(
+            name.to_string(),
+            calc_range(original_input, name_start, name_len),
+            value,
+            range,
+        ),
+    ))
+}
+
 fn parse_atomic_type(input: &str) -> IResult<&str, TypeAnnotation> {
     alt((parse_generic_type, parse_tuple_type, parse_simple_type))(input)
 }
@@ -402,24 +605,82 @@ fn parse_function_type(input: &str) -> IResult<&str, TypeAnnotation> {
     ))
 }
 
-pub fn parse_file(input: &str) -> std::result::Result<AmaroFile, String> {
+pub fn parse_file(input: &str) -> std::result::Result<AmaroFile, ParseError> {
+    parse_file_with_config(input, &AnalysisConfig::default())
+}
+
+/// Skips whitespace, comments and `{{ ... }}` blocks between top-level items,
+/// pushing each embedded Rust block onto `rust_blocks`.
+fn skip_top_level_trivia<'a>(
+    original_input: &'a str,
+    mut input: &'a str,
+    rust_blocks: &mut Vec<RustBlock>,
+) -> &'a str {
+    loop {
+        if let Ok((rest, _)) = recognize(many0(alt((
+            multispace1::<&str, Error<&str>>,
+            recognize(pair(tag("//"), not_line_ending)),
+        ))))(input)
+        {
+            input = rest;
+        }
+
+        match parse_rust_embedded_robust(input) {
+            Ok((rest, code)) => {
+                let start = code.as_ptr() as usize - original_input.as_ptr() as usize;
+                rust_blocks.push(RustBlock::new(
+                    code.to_string(),
+                    calc_range(original_input, start, code.len()),
+                ));
+                input = rest;
+            }
+            Err(_) => return input,
+        }
+    }
+}
+
+/// Parses a whole document, recognizing the block kinds listed in `config`.
+pub fn parse_file_with_config(
+    input: &str,
+    config: &AnalysisConfig,
+) -> std::result::Result<AmaroFile, ParseError> {
     // Commented since this was causing race condition in tests
     // reset_node_ids();
 
     let mut blocks = Vec::new();
+    let mut bindings = Vec::new();
+    let mut rust_blocks = Vec::new();
+    let mut syntax_errors = Vec::new();
     let mut current_input = input;
+    let outline_only = input.len() > config.max_file_bytes;
 
     while !current_input.is_empty() {
-        // Skip whitespace
-        if let Ok((rest, _)) = whitespace_handler(current_input) {
-            current_input = rest;
-        }
+        // Skip whitespace and comments, recording embedded Rust blocks
+        current_input = skip_top_level_trivia(input, current_input, &mut rust_blocks);
 
         if current_input.is_empty() {
             break;
         }
 
-        match parse_block(input, current_input) {
+        if outline_only
+            && current_input
+                .strip_prefix("let")
+                .is_some_and(|rest| rest.starts_with(char::is_whitespace))
+        {
+            // Skip the binding's line rather than parsing its expression
+            current_input = current_input
+                .find('\n')
+                .map_or("", |pos| &current_input[pos + 1..]);
+            continue;
+        }
+
+        if let Ok((rest, binding)) = parse_top_level_let(input, current_input, &mut syntax_errors) {
+            bindings.push(binding);
+            current_input = rest;
+            continue;
+        }
+
+        match parse_block(input, current_input, &mut syntax_errors, config) {
             Ok((rest, Some(block))) => {
                 blocks.push(block);
                 current_input = rest;
@@ -445,5 +706,10 @@ pub fn parse_file(input: &str) -> std::result::Result<AmaroFile, String> {
         }
     }
 
-    Ok(AmaroFile::new(blocks))
+    let mut file = AmaroFile::new(blocks);
+    file.bindings = bindings;
+    file.rust_blocks = rust_blocks;
+    file.syntax_errors = syntax_errors;
+    file.outline_only = outline_only;
+    Ok(file)
 }
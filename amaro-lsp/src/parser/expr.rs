@@ -2,34 +2,55 @@ use nom::error::Error;
 use nom::{
     IResult,
     branch::alt,
-    bytes::complete::{tag, take_while},
+    bytes::complete::{tag, take_while, take_while1},
     character::complete::{char, digit1},
-    combinator::{map, opt, peek, recognize, value},
+    combinator::{map, not, opt, peek, recognize, value},
     multi::{many0, separated_list0},
-    sequence::{pair, terminated, tuple},
+    sequence::{pair, preceded, terminated, tuple},
 };
 
 use super::utils::calc_range;
 use crate::ast::*;
 
 use super::core::{
-    is_keyword, parse_identifier, parse_non_keyword_identifier, whitespace_handler, ws,
+    is_keyword, parse_identifier, parse_non_keyword_identifier, parse_raw_identifier,
+    whitespace_handler, ws,
 };
 
 const MAX_RECURSION_DEPTH: usize = 100;
 
+/// Stack budget for one expression. Unoptimized builds use tens of KB per
+/// nesting level, so the depth limit alone can overflow a 2 MB thread stack.
+const MAX_STACK_BYTES: usize = 1024 * 1024;
+
 // Expression Parsing
 struct ParseContext {
     depth: usize,
+    stack_base: usize,
+    /// Offsets where a `let` binding was missing its `in`.
+    missing_in: Vec<usize>,
+}
+
+/// Approximate current stack position.
+#[inline(never)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
 }
 
 impl ParseContext {
     fn new() -> Self {
-        ParseContext { depth: 0 }
+        ParseContext {
+            depth: 0,
+            stack_base: stack_address(),
+            missing_in: Vec::new(),
+        }
     }
 
     fn check_depth(&self) -> Result<(), nom::Err<Error<&'static str>>> {
-        if self.depth >= MAX_RECURSION_DEPTH {
+        if self.depth >= MAX_RECURSION_DEPTH
+            || stack_address().abs_diff(self.stack_base) > MAX_STACK_BYTES
+        {
             Err(nom::Err::Error(Error::new(
                 "",
                 nom::error::ErrorKind::TooLarge,
@@ -50,11 +71,27 @@ impl ParseContext {
     }
 }
 
-pub fn parse_expr<'a>(original_input: &'a str, input: &'a str) -> IResult<&'a str, Expr> {
+/// Parses one expression, recording recoverable syntax errors in `errors`
+/// if it succeeds.
+pub fn parse_expr<'a>(
+    original_input: &'a str,
+    input: &'a str,
+    errors: &mut Vec<SyntaxError>,
+) -> IResult<&'a str, Expr> {
     let (input, _) = whitespace_handler(input)?;
 
     let mut ctx = ParseContext::new();
-    parse_expr_with_context(original_input, input, &mut ctx)
+    let (input, expr) = parse_expr_with_context(original_input, input, &mut ctx)?;
+
+    // Backtracking can reach the same `let` more than once
+    ctx.missing_in.sort_unstable();
+    ctx.missing_in.dedup();
+    errors.extend(ctx.missing_in.into_iter().map(|offset| SyntaxError {
+        message: "Expected 'in' after let binding".to_string(),
+        range: calc_range(original_input, offset, 0),
+    }));
+
+    Ok((input, expr))
 }
 
 fn parse_expr_with_context<'a>(
@@ -74,16 +111,18 @@ fn parse_let_expr<'a>(
     input: &'a str,
     ctx: &mut ParseContext,
 ) -> IResult<&'a str, Expr> {
-    let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
-
     // 1. Consume whitespace before 'let'
     let (input, _) = whitespace_handler(input)?;
-    let (input, is_let) = opt(tag("let"))(input)?;
+    let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
+    let (input, is_let) = opt(keyword("let"))(input)?;
 
     if is_let.is_some() {
         // 2. Whitespace after 'let'
         let (input, _) = whitespace_handler(input)?;
-        let (input, name) = parse_non_keyword_identifier(input)?;
+        let name_start = input.as_ptr() as usize - original_input.as_ptr() as usize;
+        let (rest, name) = parse_non_keyword_identifier(input)?;
+        let name_range = calc_range(original_input, name_start, input.len() - rest.len());
+        let input = rest;
 
         // 3. Handle '=' with whitespace around it
         let (input, _) = whitespace_handler(input)?;
@@ -92,9 +131,15 @@ fn parse_let_expr<'a>(
 
         let (input, value) = parse_if_expr(original_input, input, ctx)?;
 
-        // 4. Handle 'in' with whitespace around it
+        // 4. Handle 'in' with whitespace around it. Without it, report the
+        // missing 'in' right after the value and recover with the value alone.
+        let after_value = input;
         let (input, _) = whitespace_handler(input)?;
-        let (input, _) = tag("in")(input)?;
+        let Ok((input, _)) = keyword("in")(input) else {
+            ctx.missing_in
+                .push(after_value.as_ptr() as usize - original_input.as_ptr() as usize);
+            return Ok((after_value, value));
+        };
         let (input, _) = whitespace_handler(input)?;
 
         let (input, body) = parse_expr_with_context(original_input, input, ctx)?;
@@ -106,6 +151,7 @@ fn parse_let_expr<'a>(
             Expr::new(
                 ExprKind::LetBinding {
                     name: name.to_string(),
+                    name_range,
                     value: Box::new(value),
                     body: Box::new(body),
                 },
@@ -122,47 +168,78 @@ fn parse_if_expr<'a>(
     input: &'a str,
     ctx: &mut ParseContext,
 ) -> IResult<&'a str, Expr> {
+    // 1. Consume whitespace before 'if'
+    let (input, _) = whitespace_handler(input)?;
     let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
 
-    // 1. Consume whitespace before 'if'
+    match keyword("if")(input) {
+        Ok((input, _)) => parse_if_rest(original_input, input, start, ctx),
+        Err(_) => parse_lambda_expr(original_input, input, ctx),
+    }
+}
+
+/// Parses the rest of an `if` (or `elif`) starting at `start`, after the
+/// keyword: `cond then a else b`. `elif cond then ...` in place of `else`
+/// is sugar for `else if cond then ...`.
+fn parse_if_rest<'a>(
+    original_input: &'a str,
+    input: &'a str,
+    start: usize,
+    ctx: &mut ParseContext,
+) -> IResult<&'a str, Expr> {
+    // 2. Whitespace after 'if'
     let (input, _) = whitespace_handler(input)?;
-    let (input, is_if) = opt(tag("if"))(input)?;
+    let (input, condition) = parse_lambda_expr(original_input, input, ctx)?;
 
-    if is_if.is_some() {
-        // 2. Whitespace after 'if'
-        let (input, _) = whitespace_handler(input)?;
-        let (input, condition) = parse_lambda_expr(original_input, input, ctx)?;
+    // 3. Handle 'then' with whitespace around it
+    let (input, _) = whitespace_handler(input)?;
+    let (input, _) = keyword("then")(input)?;
+    let (input, _) = whitespace_handler(input)?;
 
-        // 3. Handle 'then' with whitespace around it
-        let (input, _) = whitespace_handler(input)?;
-        let (input, _) = tag("then")(input)?;
-        let (input, _) = whitespace_handler(input)?;
+    let (input, then_branch) = parse_if_expr(original_input, input, ctx)?;
 
-        let (input, then_branch) = parse_if_expr(original_input, input, ctx)?;
+    // 4. Handle 'else' or 'elif' with whitespace around it
+    let (input, _) = whitespace_handler(input)?;
+    let (input, else_branch) = match keyword("elif")(input) {
+        Ok((rest, _)) => {
+            let elif_start = input.as_ptr() as usize - original_input.as_ptr() as usize;
+            parse_if_rest(original_input, rest, elif_start, ctx)?
+        }
+        Err(_) => {
+            let (input, _) = keyword("else")(input)?;
+            let (input, _) = whitespace_handler(input)?;
+            parse_if_expr(original_input, input, ctx)?
+        }
+    };
 
-        // 4. Handle 'else' with whitespace around it
-        let (input, _) = whitespace_handler(input)?;
-        let (input, _) = tag("else")(input)?;
-        let (input, _) = whitespace_handler(input)?;
+    let end = input.as_ptr() as usize - original_input.as_ptr() as usize;
 
-        let (input, else_branch) = parse_if_expr(original_input, input, ctx)?;
+    Ok((
+        input,
+        Expr::new(
+            ExprKind::IfThenElse {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            },
+            calc_range(original_input, start, end - start),
+        ),
+    ))
+}
 
-        let end = input.as_ptr() as usize - original_input.as_ptr() as usize;
+/// Matches the delimiter `c` that closes a node, skipping whitespace before
+/// it but not after, so the node's range ends at the delimiter.
+fn closing<'a>(c: char) -> impl FnMut(&'a str) -> IResult<&'a str, char> {
+    preceded(whitespace_handler, char(c))
+}
 
-        Ok((
-            input,
-            Expr::new(
-                ExprKind::IfThenElse {
-                    condition: Box::new(condition),
-                    then_branch: Box::new(then_branch),
-                    else_branch: Box::new(else_branch),
-                },
-                calc_range(original_input, start, end - start),
-            ),
-        ))
-    } else {
-        parse_lambda_expr(original_input, input, ctx)
-    }
+/// Matches the keyword `word`, but not as the start of a longer identifier
+/// such as `iffy`.
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(
+        tag(word),
+        not(take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_')),
+    )
 }
 
 fn parse_lambda_expr<'a>(
@@ -170,10 +247,9 @@ fn parse_lambda_expr<'a>(
     input: &'a str,
     ctx: &mut ParseContext,
 ) -> IResult<&'a str, Expr> {
-    let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
-
     // 1. Whitespace before pipe '|'
     let (input, _) = whitespace_handler(input)?;
+    let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
     let (input, is_lambda) = opt(char('|'))(input)?;
 
     if is_lambda.is_some() {
@@ -322,15 +398,47 @@ fn parse_multiplicative_expr<'a>(
         original_input,
         input,
         ctx,
-        |o, i, c| parse_unary_expr(o, i, c),
+        |o, i, c| parse_power_expr(o, i, c),
         alt((
-            value(BinaryOperator::Mul, ws(char('*'))),
+            value(
+                BinaryOperator::Mul,
+                ws(terminated(char('*'), not(char('*')))),
+            ),
             value(BinaryOperator::Div, ws(char('/'))),
             value(BinaryOperator::Mod, ws(char('%'))),
         )),
     )
 }
 
+/// Parses `base ** exponent`, which is right-associative: `2 ** 3 ** 2` is
+/// `2 ** (3 ** 2)`.
+fn parse_power_expr<'a>(
+    original_input: &'a str,
+    input: &'a str,
+    ctx: &mut ParseContext,
+) -> IResult<&'a str, Expr> {
+    let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
+    let (input, base) = parse_unary_expr(original_input, input, ctx)?;
+
+    let Ok((rest, _)) = ws(tag::<&str, &str, Error<&str>>("**"))(input) else {
+        return Ok((input, base));
+    };
+    let (rest, exponent) = parse_power_expr(original_input, rest, ctx)?;
+    let end = rest.as_ptr() as usize - original_input.as_ptr() as usize;
+
+    Ok((
+        rest,
+        Expr::new(
+            ExprKind::BinaryOp {
+                op: BinaryOperator::Pow,
+                left: Box::new(base),
+                right: Box::new(exponent),
+            },
+            calc_range(original_input, start, end - start),
+        ),
+    ))
+}
+
 fn parse_binary_op<'a, F, G>(
     original_input: &'a str,
     input: &'a str,
@@ -345,8 +453,13 @@ where
     let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
     let (input, left) = next_level(original_input, input, ctx)?;
 
-    let (input, ops_and_rights) =
-        many0(pair(&mut op_parser, |i| next_level(original_input, i, ctx)))(input)?;
+    // Each right operand is paired with the offset where it ends, so every
+    // node of the left-leaning tree spans exactly its own operands.
+    let (input, ops_and_rights) = many0(pair(&mut op_parser, |i| {
+        let (rest, right) = next_level(original_input, i, ctx)?;
+        let end = rest.as_ptr() as usize - original_input.as_ptr() as usize;
+        Ok((rest, (right, end)))
+    }))(input)?;
 
     if ops_and_rights.is_empty() {
         return Ok((input, left));
@@ -355,8 +468,7 @@ where
     let mut result = left;
     let current_start = start;
 
-    for (op, right) in ops_and_rights {
-        let end = input.as_ptr() as usize - original_input.as_ptr() as usize;
+    for (op, (right, end)) in ops_and_rights {
         result = Expr::new(
             ExprKind::BinaryOp {
                 op,
@@ -385,7 +497,7 @@ fn parse_unary_expr<'a>(
     match op_parse {
         Ok((rest, op)) => {
             let (rest, operand) = parse_unary_expr(original_input, rest, ctx)?;
-            let end = operand.range.end.character as usize;
+            let end = rest.as_ptr() as usize - original_input.as_ptr() as usize;
             Ok((
                 rest,
                 Expr::new(
@@ -406,15 +518,15 @@ fn parse_postfix_expr<'a>(
     input: &'a str,
     ctx: &mut ParseContext,
 ) -> IResult<&'a str, Expr> {
+    let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
     let (mut current_input, mut base) = parse_primary_expr(original_input, input, ctx)?;
-    let start = base.range.start.character as usize;
 
     loop {
         if let Ok((rest, _)) = ws(char('.'))(current_input) {
             // Tuple Projection / Dynamic Indexing with Parentheses
             if let Ok((rest_inner, _)) = tag::<_, _, Error<&str>>("(")(rest) {
                 // Tuple Projection .(0)
-                if let Ok((rest_idx, idx_str)) = terminated(digit1, ws(char(')')))(rest_inner) {
+                if let Ok((rest_idx, idx_str)) = terminated(digit1, closing(')'))(rest_inner) {
                     let idx = idx_str.parse::<usize>().unwrap_or(0);
                     let end = rest_idx.as_ptr() as usize - original_input.as_ptr() as usize;
 
@@ -432,7 +544,7 @@ fn parse_postfix_expr<'a>(
                 // Dynamic Indexing .(expr)
                 let (rest_final, index_expr) = terminated(
                     |i| parse_expr_with_context(original_input, i, ctx),
-                    ws(char(')')),
+                    closing(')'),
                 )(rest_inner)?;
 
                 let end = rest_final.as_ptr() as usize - original_input.as_ptr() as usize;
@@ -449,7 +561,9 @@ fn parse_postfix_expr<'a>(
             }
 
             // Field access or Tuple Indexing
-            if let Ok((rest_inner, field)) = alt((parse_identifier, digit1))(rest) {
+            if let Ok((rest_inner, field)) =
+                alt((parse_raw_identifier, parse_identifier, digit1))(rest)
+            {
                 let end = rest_inner.as_ptr() as usize - original_input.as_ptr() as usize;
                 base = Expr::new(
                     ExprKind::FieldAccess {
@@ -466,7 +580,7 @@ fn parse_postfix_expr<'a>(
         // Indexing
         if let Ok((rest, _)) = ws(char('['))(current_input) {
             let (rest, index_expr) = parse_expr_with_context(original_input, rest, ctx)?;
-            let (rest, _) = ws(char(']'))(rest)?;
+            let (rest, _) = closing(']')(rest)?;
 
             let end = rest.as_ptr() as usize - original_input.as_ptr() as usize;
             base = Expr::new(
@@ -485,7 +599,8 @@ fn parse_postfix_expr<'a>(
             let (rest, args) = separated_list0(ws(char(',')), |i| {
                 parse_expr_with_context(original_input, i, ctx)
             })(rest)?;
-            let (rest, _) = ws(char(')'))(rest)?;
+            let (rest, _) = trailing_comma(&args, rest)?;
+            let (rest, _) = closing(')')(rest)?;
 
             let end = rest.as_ptr() as usize - original_input.as_ptr() as usize;
             base = Expr::new(
@@ -512,16 +627,16 @@ fn parse_primary_expr<'a>(
 ) -> IResult<&'a str, Expr> {
     let start: usize = input.as_ptr() as usize - original_input.as_ptr() as usize;
 
-    if let Ok((rest, _)) = ws(tag("None"))(input) {
+    if let Ok((rest, _)) = keyword("None")(input) {
         return Ok((
             rest,
             Expr::new(ExprKind::None, calc_range(original_input, start, 4)),
         ));
     }
-    if let Ok((rest, _)) = ws(tag("true"))(input) {
+    if let Ok((rest, _)) = keyword("true")(input) {
         return Ok((rest, Expr::bool(true, calc_range(original_input, start, 4))));
     }
-    if let Ok((rest, _)) = ws(tag("false"))(input) {
+    if let Ok((rest, _)) = keyword("false")(input) {
         return Ok((
             rest,
             Expr::bool(false, calc_range(original_input, start, 5)),
@@ -534,10 +649,10 @@ fn parse_primary_expr<'a>(
         return Ok((rest, val));
     }
 
-    if let Ok((rest, _)) = ws(tag("Some"))(input) {
+    if let Ok((rest, _)) = keyword("Some")(input) {
         let (rest, _) = ws(char('('))(rest)?;
         let (rest, expr) = parse_expr_with_context(original_input, rest, ctx)?;
-        let (rest, _) = ws(char(')'))(rest)?;
+        let (rest, _) = closing(')')(rest)?;
 
         let end = rest.as_ptr() as usize - original_input.as_ptr() as usize;
         return Ok((
@@ -554,7 +669,8 @@ fn parse_primary_expr<'a>(
         let (rest, exprs) = separated_list0(ws(char(',')), |i| {
             parse_expr_with_context(original_input, i, ctx)
         })(rest)?;
-        let (rest, _) = ws(char(']'))(rest)?;
+        let (rest, _) = trailing_comma(&exprs, rest)?;
+        let (rest, _) = closing(']')(rest)?;
 
         let end = rest.as_ptr() as usize - original_input.as_ptr() as usize;
         return Ok((
@@ -571,10 +687,12 @@ fn parse_primary_expr<'a>(
         let (rest, exprs) = separated_list0(ws(char(',')), |i| {
             parse_expr_with_context(original_input, i, ctx)
         })(rest)?;
-        let (rest, _) = ws(char(')'))(rest)?;
+        let (rest, has_trailing_comma) = trailing_comma(&exprs, rest)?;
+        let (rest, _) = closing(')')(rest)?;
 
         let end = rest.as_ptr() as usize - original_input.as_ptr() as usize;
-        if exprs.len() == 1 {
+        // `(x)` is grouping; `(x,)` is a one-element tuple.
+        if exprs.len() == 1 && !has_trailing_comma {
             return Ok((rest, exprs.into_iter().next().unwrap()));
         } else {
             return Ok((
@@ -587,6 +705,18 @@ fn parse_primary_expr<'a>(
         }
     }
 
+    // Escaped keyword used as a plain identifier
+    if let Ok((rest, name)) = parse_raw_identifier(input) {
+        let end = rest.as_ptr() as usize - original_input.as_ptr() as usize;
+        return Ok((
+            rest,
+            Expr::identifier(
+                name.to_string(),
+                calc_range(original_input, start, end - start),
+            ),
+        ));
+    }
+
     // Struct literal
     let (rest_after_id, id_str) = parse_identifier(input)?;
     if let Ok((_, _)) = peek(ws(char('{')))(rest_after_id)
@@ -602,7 +732,8 @@ fn parse_primary_expr<'a>(
                 |(name, _, expr)| (name.to_string(), expr),
             ),
         )(rest)?;
-        let (rest, _) = ws(char('}'))(rest)?;
+        let (rest, _) = trailing_comma(&fields, rest)?;
+        let (rest, _) = closing('}')(rest)?;
 
         let end = rest.as_ptr() as usize - original_input.as_ptr() as usize;
         return Ok((
@@ -628,8 +759,9 @@ fn parse_number<'a>(original_input: &'a str) -> impl FnMut(&'a str) -> IResult<&
     move |input: &'a str| {
         let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
 
-        if let Ok((input, float_str)) = recognize::<_, _, Error<&str>, _>(tuple((
-            opt(char('-')),
+        // A leading '-' is left to `parse_unary_expr`, so `-3.0` and `- 3.0`
+        // both become `Neg(3.0)`
+        if let Ok((input, float_str)) = recognize::<_, _, Error<&str>, _>(pair(
             digit1,
             alt((
                 recognize(tuple((
@@ -647,7 +779,7 @@ fn parse_number<'a>(original_input: &'a str) -> impl FnMut(&'a str) -> IResult<&
                     digit1,
                 ))),
             )),
-        )))(input)
+        ))(input)
         {
             let len = float_str.len();
             if let Ok(value) = float_str.parse::<f64>() {
@@ -658,10 +790,23 @@ fn parse_number<'a>(original_input: &'a str) -> impl FnMut(&'a str) -> IResult<&
             }
         }
 
-        let (input, int_str) = recognize(tuple((opt(char('-')), digit1)))(input)?;
+        let (input, int_str) = alt((
+            recognize(pair(
+                alt((tag("0x"), tag("0X"))),
+                take_while1(|c: char| c.is_ascii_hexdigit() || c == '_'),
+            )),
+            recognize(pair(
+                alt((tag("0b"), tag("0B"))),
+                take_while1(|c: char| c == '0' || c == '1' || c == '_'),
+            )),
+            recognize(pair(
+                digit1,
+                take_while(|c: char| c.is_ascii_digit() || c == '_'),
+            )),
+        ))(input)?;
         let len = int_str.len();
 
-        if let Ok(value) = int_str.parse::<i64>() {
+        if let Some(value) = parse_int_literal(int_str) {
             Ok((
                 input,
                 Expr::int(value, calc_range(original_input, start, len)),
@@ -675,21 +820,69 @@ fn parse_number<'a>(original_input: &'a str) -> impl FnMut(&'a str) -> IResult<&
     }
 }
 
+/// Consumes an optional trailing comma after a non-empty element list.
+fn trailing_comma<'a, T>(items: &[T], input: &'a str) -> IResult<&'a str, bool> {
+    if items.is_empty() {
+        return Ok((input, false));
+    }
+    let (input, comma) = opt(ws(char(',')))(input)?;
+    Ok((input, comma.is_some()))
+}
+
+/// Converts decimal, `0x` hex, or `0b` binary digits (with optional `_`
+/// separators) into an `i64`.
+fn parse_int_literal(literal: &str) -> Option<i64> {
+    let (radix, digits) = match literal.get(..2) {
+        Some("0x" | "0X") => (16, &literal[2..]),
+        Some("0b" | "0B") => (2, &literal[2..]),
+        _ => (10, literal),
+    };
+
+    let cleaned: String = digits.chars().filter(|&c| c != '_').collect();
+    i64::from_str_radix(&cleaned, radix).ok()
+}
+
+/// Parses a `'...'` or `"..."` string literal, decoding `\n`, `\t`, `\\`, and
+/// escaped quotes. Strings may not span lines; an unterminated string fails
+/// without consuming input so the caller can recover.
 fn parse_string_literal<'a>(
     original_input: &'a str,
 ) -> impl FnMut(&'a str) -> IResult<&'a str, Expr> {
     move |input: &'a str| {
         let start = input.as_ptr() as usize - original_input.as_ptr() as usize;
 
-        let (input, _) = char('\'')(input)?;
-        let (input, content) = take_while(|c| c != '\'')(input)?;
-        let (input, _) = char('\'')(input)?;
-
-        let len = content.len() + 2;
+        let (body, quote) = alt((char('\''), char('"')))(input)?;
+
+        let mut content = String::new();
+        let mut chars = body.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    // Opening quote + body + closing quote
+                    let len = i + 2;
+                    return Ok((
+                        &input[len..],
+                        Expr::string(content, calc_range(original_input, start, len)),
+                    ));
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => content.push('\n'),
+                    Some((_, 't')) => content.push('\t'),
+                    Some((_, escaped @ ('\\' | '\'' | '"'))) => content.push(escaped),
+                    Some((_, '\n')) | None => break,
+                    Some((_, other)) => {
+                        content.push('\\');
+                        content.push(other);
+                    }
+                },
+                '\n' => break,
+                c => content.push(c),
+            }
+        }
 
-        Ok((
+        Err(nom::Err::Error(Error::new(
             input,
-            Expr::string(content.to_string(), calc_range(original_input, start, len)),
-        ))
+            nom::error::ErrorKind::Escaped,
+        )))
     }
 }
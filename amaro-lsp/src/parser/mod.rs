@@ -1,8 +1,12 @@
+pub mod config;
 pub mod core;
 pub mod expr;
+pub mod navigation;
 pub mod semantics;
 pub mod symbols;
+pub mod types;
 pub mod utils;
 
+pub use config::{AnalysisConfig, RuleSeverity};
 pub use core::*;
 pub use semantics::*;
@@ -0,0 +1,68 @@
+use tower_lsp::lsp_types::{Position, Range};
+
+use crate::ast::{AmaroFile, Block, Expr, ExprKind};
+
+/// Returns the innermost expression whose range contains `pos`.
+///
+/// AST ranges are already LSP positions, so no byte conversion is needed.
+/// Where two sibling ranges touch, the later one wins, matching a cursor
+/// placed on the first character of the right-hand node.
+pub fn node_at_position(file: &AmaroFile, pos: Position) -> Option<&Expr> {
+    nodes_at_position(file, pos).pop()
+}
+
+/// Returns the expressions whose ranges contain `pos`, from the top-level
+/// field or `let` value down to the innermost one.
+pub fn nodes_at_position(file: &AmaroFile, pos: Position) -> Vec<&Expr> {
+    let mut path = Vec::new();
+    let mut current = file.values().find(|expr| contains(expr.range, pos));
+    while let Some(expr) = current {
+        path.push(expr);
+        current = expr
+            .children()
+            .into_iter()
+            .rev()
+            .find(|child| contains(child.range, pos));
+    }
+    path
+}
+
+/// Returns the name under `pos` and its range: a block header, a `let`
+/// name, a struct literal's name, or an identifier.
+pub fn name_at_position(file: &AmaroFile, pos: Position) -> Option<(&str, Range)> {
+    if let Some(block) = file.blocks.iter().find(|block| contains(block.range, pos)) {
+        return Some((&block.kind, block.range));
+    }
+    if let Some(binding) = file
+        .bindings
+        .iter()
+        .find(|binding| contains(binding.name_range, pos))
+    {
+        return Some((&binding.name, binding.name_range));
+    }
+
+    let node = node_at_position(file, pos)?;
+    match &node.kind {
+        ExprKind::Identifier(name) => Some((name, node.range)),
+        ExprKind::LetBinding {
+            name, name_range, ..
+        } if contains(*name_range, pos) => Some((name, *name_range)),
+        ExprKind::StructLiteral { name, .. } => {
+            let start = node.range.start;
+            let end = Position::new(start.line, start.character + name.len() as u32);
+            contains(Range::new(start, end), pos).then_some((name, Range::new(start, end)))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the block whose outer range contains `pos`.
+pub fn block_at_position(file: &AmaroFile, pos: Position) -> Option<&Block> {
+    file.blocks
+        .iter()
+        .find(|block| contains(block.outer_range, pos))
+}
+
+fn contains(range: Range, pos: Position) -> bool {
+    range.start <= pos && pos <= range.end
+}
@@ -1,116 +1,346 @@
+use super::config::{AnalysisConfig, rule_code};
+use super::core::parse_file_with_config;
 use super::symbols::*;
+use super::types::annotation_type;
+use super::utils::closest_match;
 use crate::ast::*;
 use std::collections::HashMap;
 use tower_lsp::lsp_types::{
-    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, Range, Url,
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, Range,
 };
 
+/// Block kinds recognized by the Amaro compiler. This is the default for
+/// `AnalysisConfig::known_blocks`, which block recovery and the
+/// unknown-block check both read.
+pub const KNOWN_BLOCKS: [&str; 9] = [
+    "GateRealization",
+    "Transition",
+    "Architecture",
+    "Arch",
+    "Step",
+    "RouteInfo",
+    "TransitionInfo",
+    "ArchInfo",
+    "StateInfo",
+];
+
+/// Blocks every Amaro program must define.
+pub const MANDATORY_BLOCKS: [&str; 2] = ["RouteInfo", "TransitionInfo"];
+
+/// Returns the fields a block of the given kind must define.
+pub fn required_fields(kind: &str) -> &'static [&'static str] {
+    match kind {
+        "RouteInfo" => &["routed_gates", "realize_gate"],
+        "TransitionInfo" => &["get_transitions", "apply", "cost"],
+        _ => &[],
+    }
+}
+
+/// The context variable through which a block's own fields are read, e.g.
+/// `Transition.cost` for `TransitionInfo`'s `cost`.
+fn self_context_var(kind: &str) -> Option<&'static str> {
+    match kind {
+        "TransitionInfo" => Some("Transition"),
+        "StateInfo" => Some("State"),
+        "ArchInfo" => Some("Arch"),
+        _ => None,
+    }
+}
+
+/// Parses `source` and returns its syntax and semantic diagnostics.
+///
+/// This is the pipeline the language server publishes on every change.
+pub fn diagnose(source: &str) -> Vec<Diagnostic> {
+    diagnose_with_config(source, &AnalysisConfig::default())
+}
+
+/// Like [`diagnose`], but parses and checks against `config`.
+pub fn diagnose_with_config(source: &str, config: &AnalysisConfig) -> Vec<Diagnostic> {
+    diagnose_parsed(&parse_file_with_config(source, config), config)
+}
+
+/// Builds the diagnostics for a document that has already been parsed.
+pub fn diagnose_parsed(
+    parsed: &Result<AmaroFile, ParseError>,
+    config: &AnalysisConfig,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    match parsed {
+        Ok(file) => {
+            for error in &file.syntax_errors {
+                diagnostics.push(Diagnostic {
+                    range: error.range,
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    message: error.message.clone(),
+                    ..Default::default()
+                });
+            }
+
+            if file.outline_only {
+                diagnostics.push(Diagnostic {
+                    range: Range::default(),
+                    severity: Some(DiagnosticSeverity::INFORMATION),
+                    message: "File too large; deep analysis skipped.".to_string(),
+                    ..Default::default()
+                });
+                diagnostics.append(&mut check_block_structure(file, config));
+            } else {
+                diagnostics.append(&mut check_semantics_with_config(file, config));
+            }
+        }
+        Err(e) => {
+            diagnostics.push(Diagnostic {
+                range: e.range,
+                severity: Some(DiagnosticSeverity::ERROR),
+                message: format!("Fatal Syntax Error: Parsing aborted.\nParse error: {}", e),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
 /// Performs semantic analysis on a parsed Amaro file.
 ///
 /// Validates block structure, required fields, and type correctness.
 /// Returns diagnostics for LSP clients.
 pub fn check_semantics(file: &AmaroFile) -> Vec<Diagnostic> {
-    let mut diagnostics = Vec::new();
+    check_semantics_with_config(file, &AnalysisConfig::default())
+}
 
-    let known_blocks = [
-        "GateRealization",
-        "Transition",
-        "Architecture",
-        "Arch",
-        "Step",
-        "RouteInfo",
-        "TransitionInfo",
-        "ArchInfo",
-        "StateInfo",
-    ];
-
-    let mut required_keys: HashMap<&str, Vec<&str>> = HashMap::new();
-    required_keys.insert("RouteInfo", vec!["routed_gates", "realize_gate"]);
-    required_keys.insert("TransitionInfo", vec!["get_transitions", "apply", "cost"]);
-    required_keys.insert("ArchInfo", vec![]);
-    required_keys.insert("StateInfo", vec![]);
+/// Like [`check_semantics`], but validates block kinds against `config`.
+pub fn check_semantics_with_config(file: &AmaroFile, config: &AnalysisConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
 
     let mut found_blocks: HashMap<String, Range> = HashMap::new();
 
+    // 0. File-wide names, bound into every block's scope
+    let globals = file_globals(file, &mut diagnostics);
+
     // Block Level Validation
     for block in &file.blocks {
         let block_name = block.kind.as_str();
-        let lower_name = block_name.to_lowercase();
-
-        // 1. Capitalization Check
-        if let Some(correct_name) = known_blocks
-            .iter()
-            .find(|&&kb| kb.eq_ignore_ascii_case(block_name))
-            && block_name != *correct_name
-        {
-            diagnostics.push(Diagnostic {
-                range: block.range,
-                severity: Some(DiagnosticSeverity::WARNING),
-                message: format!(
-                    "Block '{}' should be Capitalized (e.g., '{}').",
-                    block_name, correct_name
-                ),
-                ..Default::default()
-            });
-        }
+        check_block_header(block, config, &mut found_blocks, &mut diagnostics);
 
-        // 2. Uniqueness Check
-        if let Some(first_range) = found_blocks.get(&lower_name) {
+        // 2.2. Empty Block Check
+        let BlockContent::Fields(items) = &block.content;
+        if items.is_empty() {
             diagnostics.push(Diagnostic {
                 range: block.range,
-                severity: Some(DiagnosticSeverity::ERROR),
-                message: format!("Duplicate definition of '{}' block.", block_name),
-                related_information: Some(vec![DiagnosticRelatedInformation {
-                    location: Location {
-                        uri: Url::parse("file:///previous/definition")
-                            .unwrap_or_else(|_| Url::parse("file:///unknown").unwrap()),
-                        range: *first_range,
-                    },
-                    message: "First defined here".to_string(),
-                }]),
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: rule_code("emptyBlock"),
+                message: format!("Block '{}' is empty.", block_name),
                 ..Default::default()
             });
-        } else {
-            found_blocks.insert(lower_name, block.range);
         }
 
         // 3. Type Check all fields
-        let mut sym_table = SymbolTable::new();
+        let mut sym_table = SymbolTable::for_block(block_name);
+        for (name, ty) in &globals {
+            sym_table.bind(name.clone(), ty.clone());
+        }
         let mut present_keys: Vec<&str> = Vec::new();
-        let BlockContent::Fields(items) = &block.content;
+        let mut first_fields: HashMap<&str, Range> = HashMap::new();
         for item in items {
             if let BlockItem::Field(field) = item {
                 present_keys.push(field.key.as_str());
-                infer_expr_type(&field.value, &mut sym_table, &mut diagnostics);
 
-                // 3.1. Gate Validation in 'routed_gates' fields
-                if block_name == "RouteInfo" && field.key == "routed_gates" {
-                    validate_gates(&field.value, &mut diagnostics);
+                // 2.1. Field Uniqueness Check
+                if let Some(first_range) = first_fields.get(field.key.as_str()) {
+                    diagnostics.push(Diagnostic {
+                        range: field.key_range,
+                        severity: Some(DiagnosticSeverity::ERROR),
+                        message: format!("Duplicate field '{}' in {}", field.key, block_name),
+                        related_information: first_defined_here(config, *first_range),
+                        ..Default::default()
+                    });
+                } else {
+                    first_fields.insert(field.key.as_str(), field.key_range);
                 }
-            }
-        }
 
-        // 4. Required Keys Check
-        if let Some(reqs) = required_keys.get(block_name) {
-            for req in reqs {
-                if !present_keys.contains(req) {
+                // 3.2. Gate Validation in 'routed_gates' fields
+                let value_type = if block_name == "RouteInfo" && field.key == "routed_gates" {
+                    validate_gates(&field.value, &mut sym_table, &mut diagnostics);
+                    Type::Unknown
+                } else {
+                    infer_expr_type(&field.value, &mut sym_table, &mut diagnostics)
+                };
+
+                // 3.1. Cost must be numeric (Int coerces to Float)
+                if matches!(block_name, "TransitionInfo" | "StateInfo")
+                    && field.key == "cost"
+                    && !matches!(value_type, Type::Float | Type::Int | Type::Unknown)
+                {
                     diagnostics.push(Diagnostic {
-                        range: block.range,
+                        range: field.value.range,
                         severity: Some(DiagnosticSeverity::ERROR),
+                        message: format!("'cost' must return Float, got {}.", value_type),
+                        ..Default::default()
+                    });
+                }
+
+                // 3.3. Architecture shape fields must be positive integers
+                if matches!(block_name, "ArchInfo" | "Architecture")
+                    && matches!(field.key.as_str(), "width" | "height" | "rows" | "cols")
+                {
+                    validate_arch_dimension(field, &value_type, &mut diagnostics);
+                }
+
+                // 3.4. 'apply' must produce a transition application, not a
+                // bare value
+                if block_name == "TransitionInfo"
+                    && field.key == "apply"
+                    && matches!(
+                        value_type,
+                        Type::Int | Type::Float | Type::Bool | Type::String
+                    )
+                {
+                    diagnostics.push(Diagnostic {
+                        range: field.value.range,
+                        severity: Some(DiagnosticSeverity::WARNING),
+                        code: rule_code("applyValue"),
                         message: format!(
-                            "Block '{}' is missing required field: '{}'",
-                            block_name, req
+                            "'apply' should be a transition application such as \
+                             identity_application(step), got {}.",
+                            value_type
                         ),
                         ..Default::default()
                     });
                 }
+
+                // 3.5. A field must not read itself through its own block
+                if let Some(var) = self_context_var(block_name)
+                    && reads_field(&field.value, var, &field.key)
+                {
+                    diagnostics.push(Diagnostic {
+                        range: field.value.range,
+                        severity: Some(DiagnosticSeverity::WARNING),
+                        code: rule_code("cyclicReference"),
+                        message: format!("Cyclic reference: '{}' depends on itself.", field.key),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+
+        // 4. Required Keys Check
+        for req in required_fields(block_name) {
+            if !present_keys.contains(req) {
+                diagnostics.push(Diagnostic {
+                    range: block.range,
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    message: format!(
+                        "Block '{}' is missing required field: '{}'",
+                        block_name, req
+                    ),
+                    ..Default::default()
+                });
             }
         }
     }
 
     // 5. Mandatory Blocks Check
-    let required_blocks = ["RouteInfo", "TransitionInfo"];
-    for req in required_blocks {
+    if config.require_mandatory_blocks {
+        check_mandatory_blocks(&found_blocks, &mut diagnostics);
+    }
+
+    config.apply_severities(&mut diagnostics);
+    diagnostics
+}
+
+/// Block-level checks for a file parsed as an outline: block names,
+/// duplicates and mandatory blocks, without looking inside any block.
+pub fn check_block_structure(file: &AmaroFile, config: &AnalysisConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut found_blocks: HashMap<String, Range> = HashMap::new();
+
+    for block in &file.blocks {
+        check_block_header(block, config, &mut found_blocks, &mut diagnostics);
+    }
+    if config.require_mandatory_blocks {
+        check_mandatory_blocks(&found_blocks, &mut diagnostics);
+    }
+
+    config.apply_severities(&mut diagnostics);
+    diagnostics
+}
+
+/// Checks a block's kind: its capitalization, whether it is known, and
+/// whether an earlier block in `found_blocks` already defines it.
+fn check_block_header(
+    block: &Block,
+    config: &AnalysisConfig,
+    found_blocks: &mut HashMap<String, Range>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let block_name = block.kind.as_str();
+    let lower_name = block_name.to_lowercase();
+
+    // 1. Capitalization Check
+    if let Some(correct_name) = config.canonical_block_name(block_name)
+        && block_name != correct_name
+    {
+        diagnostics.push(Diagnostic {
+            range: block.range,
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: rule_code("capitalization"),
+            message: format!(
+                "Block '{}' should be Capitalized (e.g., '{}').",
+                block_name, correct_name
+            ),
+            ..Default::default()
+        });
+    }
+
+    // 1.1. Unknown Block Check
+    if config.canonical_block_name(block_name).is_none() {
+        diagnostics.push(Diagnostic {
+            range: block.range,
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: rule_code("unknownBlock"),
+            message: format!(
+                "Unknown block kind '{}'. Expected one of: {}.",
+                block_name,
+                config.known_blocks.join(", ")
+            ),
+            ..Default::default()
+        });
+    }
+
+    // 2. Uniqueness Check
+    if let Some(first_range) = found_blocks.get(&lower_name) {
+        diagnostics.push(Diagnostic {
+            range: block.range,
+            severity: Some(DiagnosticSeverity::ERROR),
+            message: format!("Duplicate definition of '{}' block.", block_name),
+            related_information: first_defined_here(config, *first_range),
+            ..Default::default()
+        });
+    } else {
+        found_blocks.insert(lower_name, block.range);
+    }
+}
+
+/// Points a duplicate's diagnostic at the first definition, when the
+/// document's URI is known.
+fn first_defined_here(
+    config: &AnalysisConfig,
+    range: Range,
+) -> Option<Vec<DiagnosticRelatedInformation>> {
+    let uri = config.document_uri.clone()?;
+    Some(vec![DiagnosticRelatedInformation {
+        location: Location { uri, range },
+        message: "First defined here".to_string(),
+    }])
+}
+
+fn check_mandatory_blocks(
+    found_blocks: &HashMap<String, Range>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for req in MANDATORY_BLOCKS {
         if !found_blocks.contains_key(&req.to_lowercase()) {
             diagnostics.push(Diagnostic {
                 range: Range::default(),
@@ -120,20 +350,126 @@ pub fn check_semantics(file: &AmaroFile) -> Vec<Diagnostic> {
             });
         }
     }
-
-    diagnostics
 }
 
 /// Validates that gate identifiers are recognized gate types (CX, T, Pauli, PauliMeasurement).
-fn validate_gates(expr: &Expr, diagnostics: &mut Vec<Diagnostic>) {
+/// Names visible to every block: struct definitions, then top-level `let`
+/// bindings in source order. Diagnostics from the bindings' values are
+/// pushed onto `diagnostics`.
+pub fn file_globals(file: &AmaroFile, diagnostics: &mut Vec<Diagnostic>) -> Vec<(String, Type)> {
+    let mut table = SymbolTable::new();
+    let mut globals = Vec::new();
+
+    // Struct definitions; context values such as `Arch` keep their type
+    for (name, ty) in struct_definitions(file) {
+        if matches!(table.lookup(&name), None | Some(Type::Struct { .. })) {
+            table.bind(name.clone(), ty.clone());
+            globals.push((name, ty));
+        }
+    }
+
+    // Top-level bindings; each may refer to the ones before it
+    for binding in &file.bindings {
+        let ty = infer_expr_type(&binding.value, &mut table, diagnostics);
+        table.bind(binding.name.clone(), ty.clone());
+        globals.push((binding.name.clone(), ty));
+    }
+
+    globals
+}
+
+/// Collects every `Name{field : Type, ...}` definition in the file as a
+/// struct type. A later definition of the same name wins.
+fn struct_definitions(file: &AmaroFile) -> Vec<(String, Type)> {
+    let mut definitions = Vec::new();
+    for block in &file.blocks {
+        let BlockContent::Fields(items) = &block.content;
+        for item in items {
+            if let BlockItem::StructDef(def) = item {
+                let fields = def
+                    .fields
+                    .iter()
+                    .map(|param| (param.name.clone(), annotation_type(&param.type_annotation)))
+                    .collect();
+                definitions.push((
+                    def.name.clone(),
+                    Type::Struct {
+                        name: def.name.clone(),
+                        fields,
+                    },
+                ));
+            }
+        }
+    }
+    definitions
+}
+
+/// Checks an architecture shape field such as `width`: it must be an `Int`,
+/// and a literal value must be positive.
+fn validate_arch_dimension(field: &Field, value_type: &Type, diagnostics: &mut Vec<Diagnostic>) {
+    if !matches!(value_type, Type::Int | Type::Unknown) {
+        diagnostics.push(Diagnostic {
+            range: field.value.range,
+            severity: Some(DiagnosticSeverity::ERROR),
+            message: format!(
+                "Architecture {} must be Int, got {}.",
+                field.key, value_type
+            ),
+            ..Default::default()
+        });
+        return;
+    }
+
+    let literal = match &field.value.kind {
+        ExprKind::IntLiteral(n) => Some(*n),
+        ExprKind::UnaryOp {
+            op: UnaryOperator::Neg,
+            operand,
+        } => match operand.kind {
+            ExprKind::IntLiteral(n) => Some(-n),
+            _ => None,
+        },
+        _ => None,
+    };
+    if literal.is_some_and(|n| n <= 0) {
+        diagnostics.push(Diagnostic {
+            range: field.value.range,
+            severity: Some(DiagnosticSeverity::ERROR),
+            message: format!("Architecture {} must be a positive integer.", field.key),
+            ..Default::default()
+        });
+    }
+}
+
+/// Checks the gates listed in a `routed_gates` field.
+///
+/// Names that aren't gate literals are errors (with a suggestion when one is
+/// close); gate literals the compiler can't route are warnings. Anything
+/// other than a name, list or tuple is type-checked as usual.
+fn validate_gates(expr: &Expr, sym_table: &mut SymbolTable, diagnostics: &mut Vec<Diagnostic>) {
     let valid_gates = ["CX", "T", "Pauli", "PauliMeasurement"];
 
     match &expr.kind {
         ExprKind::Identifier(name) => {
-            if !valid_gates.contains(&name.as_str()) {
+            let gate_literals = SymbolTable::gate_literals();
+            if !gate_literals.contains_key(name) {
+                let mut message = format!("Unknown gate '{}'.", name);
+                if let Some(suggestion) =
+                    closest_match(name, gate_literals.keys().map(String::as_str), 2)
+                {
+                    message.push_str(&format!(" Did you mean '{}'?", suggestion));
+                }
+                diagnostics.push(Diagnostic {
+                    range: expr.range,
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    message,
+                    ..Default::default()
+                });
+            } else if !valid_gates.contains(&name.as_str()) {
                 diagnostics.push(Diagnostic {
                     range: expr.range,
                     severity: Some(DiagnosticSeverity::WARNING),
+                    code: rule_code("nonstandardGate"),
                     message: format!(
                         "'{}' is not a recognized standard gate. Expected one of: {:?}",
                         name, valid_gates
@@ -144,10 +480,12 @@ fn validate_gates(expr: &Expr, diagnostics: &mut Vec<Diagnostic>) {
         }
         ExprKind::List(items) | ExprKind::Tuple(items) => {
             for item in items {
-                validate_gates(item, diagnostics);
+                validate_gates(item, sym_table, diagnostics);
             }
         }
-        _ => {}
+        _ => {
+            infer_expr_type(expr, sym_table, diagnostics);
+        }
     }
 }
 
@@ -173,10 +511,23 @@ pub fn infer_expr_type(
             }
 
             sym_table.lookup(name).cloned().unwrap_or_else(|| {
+                let message = if sym_table.is_pending(name) {
+                    format!("Cannot use '{}' before it is defined.", name)
+                } else if let Some(kind) = sym_table.unavailable_context(name) {
+                    format!("'{}' is not available in {}.", name, kind)
+                } else {
+                    let mut message = format!("Undefined variable '{}'.", name);
+                    // Short names are within two edits of too much to be useful.
+                    let max_distance = 2.min(name.chars().count().saturating_sub(1));
+                    if let Some(suggestion) = closest_match(name, sym_table.names(), max_distance) {
+                        message.push_str(&format!(" Did you mean '{}'?", suggestion));
+                    }
+                    message
+                };
                 diagnostics.push(Diagnostic {
                     range: expr.range,
                     severity: Some(DiagnosticSeverity::ERROR),
-                    message: format!("Undefined variable '{}'.", name),
+                    message,
                     ..Default::default()
                 });
                 Type::Unknown
@@ -217,27 +568,33 @@ pub fn infer_expr_type(
         }
 
         ExprKind::Lambda { params, body } => {
-            sym_table.enter_scope();
-            let mut param_types = Vec::new();
-            for param in params {
-                sym_table.bind(param.clone(), Type::Unknown);
-                param_types.push(Type::Unknown);
-            }
-            let return_type = infer_expr_type(body, sym_table, diagnostics);
-            sym_table.exit_scope();
-
-            Type::Function {
-                params: param_types,
-                return_type: Box::new(return_type),
-            }
+            infer_lambda(params, &[], body, sym_table, diagnostics)
         }
 
-        ExprKind::LetBinding { name, value, body } => {
+        ExprKind::LetBinding {
+            name,
+            name_range,
+            value,
+            body,
+        } => {
             sym_table.enter_scope();
+            sym_table.push_pending(later_let_names(body));
             let value_type = infer_expr_type(value, sym_table, diagnostics);
+            sym_table.pop_pending();
             sym_table.bind(name.clone(), value_type);
             let body_type = infer_expr_type(body, sym_table, diagnostics);
             sym_table.exit_scope();
+
+            if !name.starts_with('_') && !uses_name(body, name) {
+                diagnostics.push(Diagnostic {
+                    range: *name_range,
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    code: rule_code("unusedLet"),
+                    message: format!("Unused let binding '{}'.", name),
+                    ..Default::default()
+                });
+            }
+
             body_type
         }
 
@@ -251,7 +608,7 @@ pub fn infer_expr_type(
                 diagnostics.push(Diagnostic {
                     range: condition.range,
                     severity: Some(DiagnosticSeverity::ERROR),
-                    message: "Condition in if-then-else must be of type 'Bool'.".to_string(),
+                    message: format!("If-condition must be Bool, got {}.", cond_type),
                     ..Default::default()
                 });
             }
@@ -262,13 +619,38 @@ pub fn infer_expr_type(
             if !types_compatible(&then_type, &else_type) {
                 diagnostics.push(Diagnostic {
                     range: expr.range,
-                    severity: Some(DiagnosticSeverity::ERROR),
-                    message: "Then and else branches of if-then-else must have compatible types."
-                        .to_string(),
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    code: rule_code("branchTypes"),
+                    message: format!(
+                        "Then and else branches of if-then-else have incompatible types ({} and {}).",
+                        then_type, else_type
+                    ),
                     ..Default::default()
                 });
             }
-            then_type
+
+            // A literal condition always takes the same branch
+            match condition.kind {
+                ExprKind::BoolLiteral(always) => {
+                    let (dead, dead_name) = if always {
+                        (else_branch, "else")
+                    } else {
+                        (then_branch, "then")
+                    };
+                    diagnostics.push(Diagnostic {
+                        range: dead.range,
+                        severity: Some(DiagnosticSeverity::WARNING),
+                        code: rule_code("unreachableBranch"),
+                        message: format!(
+                            "{}-branch is unreachable (condition is always {}).",
+                            dead_name, always
+                        ),
+                        ..Default::default()
+                    });
+                    if always { then_type } else { else_type }
+                }
+                _ => then_type,
+            }
         }
 
         ExprKind::FunctionCall { function, args } => {
@@ -277,36 +659,47 @@ pub fn infer_expr_type(
                 Type::Function {
                     params,
                     return_type,
+                    variadic,
                 } => {
-                    if params.len() != args.len() {
+                    if !variadic && params.len() != args.len() {
                         diagnostics.push(Diagnostic {
                             range: expr.range,
                             severity: Some(DiagnosticSeverity::ERROR),
                             message: format!(
-                                "Expected {} arguments but got {}.",
+                                "{} expects {} argument{}, got {}.",
+                                callee_name(function),
                                 params.len(),
+                                if params.len() == 1 { "" } else { "s" },
                                 args.len()
                             ),
                             ..Default::default()
                         });
-                        return *return_type;
                     }
-                    for (i, (param_type, arg)) in params.iter().zip(args).enumerate() {
-                        let arg_type = infer_expr_type(arg, sym_table, diagnostics);
+                    check_callback_arity(function, args, diagnostics);
+                    let arg_types = infer_call_args(function, args, sym_table, diagnostics);
+                    for (i, (arg, arg_type)) in args.iter().zip(&arg_types).enumerate() {
+                        let param = if variadic {
+                            params.first()
+                        } else {
+                            params.get(i)
+                        };
+                        let Some(param_type) = param else {
+                            continue;
+                        };
 
                         // Following Logic
                         // 1. If param_type Unknown, Accept
                         // 2. If arg_type Unknown, Accept (Avoid Cascading Errors)
                         // 3. Otherwise, Check Compatibility
                         if *param_type != Type::Unknown
-                            && arg_type != Type::Unknown
-                            && !types_compatible(param_type, &arg_type)
+                            && *arg_type != Type::Unknown
+                            && !types_compatible(param_type, arg_type)
                         {
                             diagnostics.push(Diagnostic {
                                 range: arg.range,
                                 severity: Some(DiagnosticSeverity::ERROR),
                                 message: format!(
-                                    "Argument {} expected type '{:?}' but got '{:?}'.",
+                                    "Argument {} expected type '{}' but got '{}'.",
                                     i + 1,
                                     param_type,
                                     arg_type
@@ -315,16 +708,42 @@ pub fn infer_expr_type(
                             });
                         }
                     }
-                    *return_type
+
+                    // `Vec(a, b, ...)` holds its arguments' type.
+                    if matches!(&function.kind, ExprKind::Identifier(name) if name == "Vec")
+                        && let Some(element) = arg_types.iter().find(|t| **t != Type::Unknown)
+                    {
+                        return Type::Vec(Box::new(element.clone()));
+                    }
+                    // `max` and `min` stay `Int` when every argument is `Int`.
+                    if matches!(&function.kind, ExprKind::Identifier(name) if name == "max" || name == "min")
+                    {
+                        return if !arg_types.is_empty() && arg_types.iter().all(|t| *t == Type::Int)
+                        {
+                            Type::Int
+                        } else {
+                            Type::Float
+                        };
+                    }
+                    callback_return_type(function, &arg_types).unwrap_or(*return_type)
                 }
-                Type::Unknown => Type::Unknown, // Avoid Cascading Errors
-                _ => {
+                Type::Unknown => {
+                    // Avoid Cascading Errors, but still check the arguments
+                    for arg in args {
+                        infer_expr_type(arg, sym_table, diagnostics);
+                    }
+                    Type::Unknown
+                }
+                other => {
                     diagnostics.push(Diagnostic {
                         range: function.range,
                         severity: Some(DiagnosticSeverity::ERROR),
-                        message: "Attempted to call a non-function value.".to_string(),
+                        message: format!("Value of type {} is not callable.", other),
                         ..Default::default()
                     });
+                    for arg in args {
+                        infer_expr_type(arg, sym_table, diagnostics);
+                    }
                     Type::Unknown
                 }
             }
@@ -332,111 +751,212 @@ pub fn infer_expr_type(
 
         ExprKind::FieldAccess { object, field } => {
             let obj_type = infer_expr_type(object, sym_table, diagnostics);
-            match obj_type {
-                Type::Vec(inner) => {
-                    if field == "push" {
-                        Type::Function {
-                            params: vec![*inner.clone()],
-                            return_type: Box::new(Type::Vec(inner.clone())),
-                        }
-                    } else if field == "pop" {
-                        Type::Function {
-                            params: vec![],
-                            return_type: Box::new(Type::Option(inner.clone())),
-                        }
-                    } else if field == "extend" {
-                        Type::Function {
-                            params: vec![Type::Vec(inner.clone())],
-                            return_type: Box::new(Type::Vec(inner.clone())),
-                        }
-                    } else if field == "is_empty" {
-                        Type::Function {
-                            params: vec![],
-                            return_type: Box::new(Type::Bool),
-                        }
-                    } else if field == "contains" {
-                        Type::Function {
-                            params: vec![*inner.clone()],
-                            return_type: Box::new(Type::Bool),
+            member_type(&obj_type, field)
+        }
+
+        ExprKind::StructLiteral { name, fields } => {
+            // Builtin struct names carry no fields until the file defines them
+            let definition = match sym_table.lookup(name) {
+                Some(Type::Struct {
+                    fields: defined, ..
+                }) if !defined.is_empty() => Some(defined.clone()),
+                _ => None,
+            };
+
+            let mut field_types = HashMap::new();
+            for (key, value) in fields {
+                let val_type = infer_expr_type(value, sym_table, diagnostics);
+
+                if let Some(defined) = &definition {
+                    let message = match defined.get(key) {
+                        None => Some(format!("Struct '{}' has no field '{}'.", name, key)),
+                        Some(expected) if !types_compatible(expected, &val_type) => {
+                            Some(option_mismatch(expected, &val_type).unwrap_or_else(|| {
+                                format!(
+                                    "Field '{}' of '{}' expects {}, got {}.",
+                                    key, name, expected, val_type
+                                )
+                            }))
                         }
-                    } else if field == "len" {
-                        Type::Int
-                    } else {
-                        Type::Unknown
+                        Some(_) => None,
+                    };
+                    if let Some(message) = message {
+                        diagnostics.push(Diagnostic {
+                            range: value.range,
+                            severity: Some(DiagnosticSeverity::ERROR),
+                            message,
+                            ..Default::default()
+                        });
                     }
                 }
-                Type::Struct { fields, .. } => fields.get(field).cloned().unwrap_or(Type::Unknown),
-                Type::Tuple(elements) => {
-                    if let Ok(idx) = field.parse::<usize>() {
-                        elements.get(idx).cloned().unwrap_or(Type::Unknown)
-                    } else {
-                        Type::Unknown
-                    }
+
+                field_types.insert(key.clone(), val_type);
+            }
+
+            if let Some(defined) = definition {
+                let mut missing: Vec<&String> = defined
+                    .keys()
+                    .filter(|key| !fields.iter().any(|(given, _)| given == *key))
+                    .collect();
+                missing.sort();
+                for key in missing {
+                    diagnostics.push(Diagnostic {
+                        range: expr.range,
+                        severity: Some(DiagnosticSeverity::ERROR),
+                        message: format!("Struct '{}' is missing field '{}'.", name, key),
+                        ..Default::default()
+                    });
                 }
 
-                // Built-in Types
-                Type::ArchT => match field.as_str() {
-                    "width" | "height" | "stack_size" => Type::Int,
-                    "edges" => Type::Function {
-                        params: vec![],
-                        return_type: Box::new(Type::Vec(Box::new(Type::Tuple(vec![
-                            Type::Location,
-                            Type::Location,
-                        ])))),
-                    },
-                    "succ_rates" => Type::Vec(Box::new(Type::Vec(Box::new(Type::Float)))),
-                    "contains_edge" => Type::Function {
-                        params: vec![Type::Tuple(vec![Type::Location, Type::Location])],
-                        return_type: Box::new(Type::Bool),
-                    },
-                    "magic_state_qubits" | "alg_qubits" => Type::Function {
-                        params: vec![],
-                        return_type: Box::new(Type::Vec(Box::new(Type::Location))),
-                    },
-                    _ => Type::Unknown,
-                },
-                Type::StateT => {
-                    match field.as_str() {
-                        // "map" => Type::QubitMap,
-                        "map" => Type::Function {
-                            params: vec![],
-                            return_type: Box::new(Type::QubitMap),
-                        },
-                        "gates" => Type::Function {
-                            params: vec![],
-                            return_type: Box::new(Type::Vec(Box::new(Type::Gate))),
-                        },
-                        "implemented_gates" => Type::Unknown,
+                return Type::Struct {
+                    name: name.clone(),
+                    fields: defined,
+                };
+            }
+
+            Type::Struct {
+                name: name.clone(),
+                fields: field_types,
+            }
+        }
+
+        ExprKind::BinaryOp { op, left, right } => {
+            let left_type = infer_expr_type(left, sym_table, diagnostics);
+            let right_type = infer_expr_type(right, sym_table, diagnostics);
+
+            // `a < b < c` compares a Bool with `c`; warn about the chain
+            // rather than the operand types. A parenthesized left operand
+            // starts after the outer expression does, so it isn't a chain.
+            if let ExprKind::BinaryOp {
+                op: inner_op,
+                left: inner_left,
+                right: inner_right,
+            } = &left.kind
+                && op.is_comparison()
+                && inner_op.is_comparison()
+                && left.range.start == expr.range.start
+            {
+                diagnostics.push(Diagnostic {
+                    range: expr.range,
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    code: rule_code("chainedComparison"),
+                    message: format!(
+                        "Chained comparison '{} {} {} {} {}' may not do what you expect; use '&&'.",
+                        inner_left.summarize(),
+                        inner_op.symbol(),
+                        inner_right.summarize(),
+                        op.symbol(),
+                        right.summarize()
+                    ),
+                    ..Default::default()
+                });
+            } else {
+                check_binary_op(op, &left_type, &right_type, expr.range, diagnostics);
+            }
+
+            if matches!(op, BinaryOperator::Div | BinaryOperator::Mod)
+                && matches!(right.kind, ExprKind::IntLiteral(0))
+            {
+                diagnostics.push(Diagnostic {
+                    range: expr.range,
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    code: rule_code("divisionByZero"),
+                    message: "Division by zero.".to_string(),
+                    ..Default::default()
+                });
+            }
+
+            match op {
+                BinaryOperator::Eq
+                | BinaryOperator::Ne
+                | BinaryOperator::Lt
+                | BinaryOperator::Le
+                | BinaryOperator::Gt
+                | BinaryOperator::Ge
+                | BinaryOperator::And
+                | BinaryOperator::Or => Type::Bool,
+                BinaryOperator::Add
+                | BinaryOperator::Sub
+                | BinaryOperator::Mul
+                | BinaryOperator::Div
+                | BinaryOperator::Mod
+                | BinaryOperator::Pow => numeric_result_type(&left_type, &right_type),
+                BinaryOperator::Range => {
+                    if let (ExprKind::IntLiteral(start), ExprKind::IntLiteral(end)) =
+                        (&left.kind, &right.kind)
+                        && start > end
+                    {
+                        diagnostics.push(Diagnostic {
+                            range: expr.range,
+                            severity: Some(DiagnosticSeverity::WARNING),
+                            code: rule_code("emptyRange"),
+                            message: format!(
+                                "Empty range: start {} is greater than end {}.",
+                                start, end
+                            ),
+                            ..Default::default()
+                        });
+                    }
+
+                    match (&left_type, &right_type) {
+                        (Type::Int, Type::Int) => Type::Vec(Box::new(Type::Int)),
                         _ => Type::Unknown,
                     }
                 }
-                Type::Gate => match field.as_str() {
-                    "qubits" => Type::Vec(Box::new(Type::Qubit)),
-                    "gate_type" => Type::Function {
-                        params: vec![],
-                        return_type: Box::new(Type::Gate),
-                    },
-                    "implementation" => Type::Unknown,
-                    "x_indices" | "y_indices" | "z_indices" => Type::Function {
-                        params: vec![],
-                        return_type: Box::new(Type::Vec(Box::new(Type::Qubit))),
-                    },
-                    _ => Type::Unknown,
-                },
-                Type::Unknown => Type::Unknown,
-                _ => Type::Unknown,
+                BinaryOperator::Tensor => {
+                    tensor_result_type(&left_type, &right_type).unwrap_or(Type::Unknown)
+                }
             }
         }
 
-        ExprKind::StructLiteral { name, fields } => {
-            let mut field_types = HashMap::new();
-            for (key, value) in fields {
-                let val_type = infer_expr_type(value, sym_table, diagnostics);
-                field_types.insert(key.clone(), val_type);
+        ExprKind::UnaryOp { op, operand } => {
+            let operand_type = infer_expr_type(operand, sym_table, diagnostics);
+            match op {
+                UnaryOperator::Not => Type::Bool,
+                UnaryOperator::Neg => {
+                    if !matches!(
+                        operand_type,
+                        Type::Int | Type::Float | Type::Location | Type::Qubit | Type::Unknown
+                    ) {
+                        diagnostics.push(Diagnostic {
+                            range: expr.range,
+                            severity: Some(DiagnosticSeverity::ERROR),
+                            message: format!("Cannot apply '-' to {}.", operand_type),
+                            ..Default::default()
+                        });
+                    }
+                    numeric_result_type(&operand_type, &operand_type)
+                }
             }
-            Type::Struct {
-                name: name.clone(),
-                fields: field_types,
+        }
+
+        ExprKind::TensorProduct { left, right } => {
+            let left_type = infer_expr_type(left, sym_table, diagnostics);
+            let right_type = infer_expr_type(right, sym_table, diagnostics);
+            let op = BinaryOperator::Tensor;
+            check_binary_op(&op, &left_type, &right_type, expr.range, diagnostics);
+            tensor_result_type(&left_type, &right_type).unwrap_or(Type::Unknown)
+        }
+
+        ExprKind::Projection { index, tuple } => {
+            match infer_expr_type(tuple, sym_table, diagnostics) {
+                Type::Tuple(elements) => match elements.get(*index) {
+                    Some(element) => element.clone(),
+                    None => {
+                        diagnostics.push(Diagnostic {
+                            range: expr.range,
+                            severity: Some(DiagnosticSeverity::ERROR),
+                            message: format!(
+                                "Tuple has {} elements; index {} out of range",
+                                elements.len(),
+                                index
+                            ),
+                            ..Default::default()
+                        });
+                        Type::Unknown
+                    }
+                },
+                _ => Type::Unknown,
             }
         }
 
@@ -456,6 +976,7 @@ pub fn infer_expr_type(
                 Type::Function {
                     params,
                     return_type,
+                    ..
                 } if params.is_empty() => match return_type.as_ref() {
                     Type::QubitMap => Type::Qubit,
                     _ => Type::Int,
@@ -468,18 +989,35 @@ pub fn infer_expr_type(
                     range: index.range,
                     severity: Some(DiagnosticSeverity::ERROR),
                     message: format!(
-                        "Index type mismatch. Expected '{:?}' but got '{:?}'.",
+                        "Index type mismatch. Expected '{}' but got '{}'.",
                         expected_idx_type, idx_type
                     ),
                     ..Default::default()
                 });
             }
 
+            // A literal index into a list literal can be checked statically
+            if let (ExprKind::List(items), ExprKind::IntLiteral(i)) = (&object.kind, &index.kind)
+                && *i >= items.len() as i64
+            {
+                diagnostics.push(Diagnostic {
+                    range: index.range,
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    message: format!(
+                        "Index {} out of bounds for list of length {}.",
+                        i,
+                        items.len()
+                    ),
+                    ..Default::default()
+                });
+            }
+
             // If object is a 0-arg function, auto-call it
             let actual_type = match obj_type {
                 Type::Function {
                     params,
                     return_type,
+                    ..
                 } if params.is_empty() => *return_type,
                 other => other,
             };
@@ -499,11 +1037,277 @@ pub fn infer_expr_type(
                 }
             }
         }
+    }
+}
+
+/// Name used for a callee in diagnostics, e.g. `value_swap` or `push`.
+fn callee_name(function: &Expr) -> String {
+    match &function.kind {
+        ExprKind::Identifier(name) => name.clone(),
+        ExprKind::FieldAccess { field, .. } => field.clone(),
+        _ => "Function".to_string(),
+    }
+}
+
+/// Names bound by the rest of a `let ... in let ... in` chain.
+fn later_let_names(mut body: &Expr) -> Vec<String> {
+    let mut names = Vec::new();
+    while let ExprKind::LetBinding {
+        name, body: next, ..
+    } = &body.kind
+    {
+        names.push(name.clone());
+        body = next;
+    }
+    names
+}
+
+/// Returns true if `name` is referenced in `expr`, ignoring uses hidden by
+/// a lambda parameter or inner `let` of the same name.
+fn uses_name(expr: &Expr, name: &str) -> bool {
+    match &expr.kind {
+        ExprKind::Identifier(ident) => ident == name,
+        ExprKind::Lambda { params, body } => {
+            !params.iter().any(|p| p == name) && uses_name(body, name)
+        }
+        ExprKind::LetBinding {
+            name: inner,
+            value,
+            body,
+            ..
+        } => uses_name(value, name) || (inner != name && uses_name(body, name)),
+        _ => expr
+            .children()
+            .into_iter()
+            .any(|child| uses_name(child, name)),
+    }
+}
 
+/// Returns true if `expr` reads `var.key` (or calls `var.key()`), unless
+/// `var` is shadowed by a lambda parameter or `let`.
+fn reads_field(expr: &Expr, var: &str, key: &str) -> bool {
+    match &expr.kind {
+        ExprKind::FieldAccess { object, field } => {
+            (field == key && matches!(&object.kind, ExprKind::Identifier(name) if name == var))
+                || reads_field(object, var, key)
+        }
+        ExprKind::Lambda { params, body } => {
+            !params.iter().any(|p| p == var) && reads_field(body, var, key)
+        }
+        ExprKind::LetBinding {
+            name, value, body, ..
+        } => reads_field(value, var, key) || (name != var && reads_field(body, var, key)),
+        _ => expr
+            .children()
+            .into_iter()
+            .any(|child| reads_field(child, var, key)),
+    }
+}
+
+/// Infers a lambda's type, binding its parameters to `param_types` (or
+/// `Unknown` past the end) while its body is checked.
+fn infer_lambda(
+    params: &[String],
+    param_types: &[Type],
+    body: &Expr,
+    sym_table: &mut SymbolTable,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Type {
+    sym_table.enter_scope();
+    let mut bound_types = Vec::new();
+    for (i, param) in params.iter().enumerate() {
+        let param_type = param_types.get(i).cloned().unwrap_or(Type::Unknown);
+        sym_table.bind(param.clone(), param_type.clone());
+        bound_types.push(param_type);
+    }
+    let return_type = infer_expr_type(body, sym_table, diagnostics);
+    sym_table.exit_scope();
+
+    Type::Function {
+        params: bound_types,
+        return_type: Box::new(return_type),
+        variadic: false,
+    }
+}
+
+/// Infers the types of a call's arguments. A lambda passed to `map` or
+/// `fold` is inferred last, with its parameters typed from the other
+/// arguments: the vector's elements, and for `fold` the accumulator.
+fn infer_call_args(
+    function: &Expr,
+    args: &[Expr],
+    sym_table: &mut SymbolTable,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Type> {
+    let callback = match &function.kind {
+        ExprKind::Identifier(name) => callback_arity(name)
+            .map(|(index, _)| (name.as_str(), index))
+            .filter(|&(_, index)| {
+                matches!(args.get(index), Some(arg) if matches!(arg.kind, ExprKind::Lambda { .. }))
+            }),
+        _ => None,
+    };
+
+    let mut arg_types = Vec::with_capacity(args.len());
+    for (i, arg) in args.iter().enumerate() {
+        if callback.is_some_and(|(_, index)| index == i) {
+            arg_types.push(Type::Unknown);
+        } else {
+            arg_types.push(infer_expr_type(arg, sym_table, diagnostics));
+        }
+    }
+
+    if let Some((name, index)) = callback
+        && let ExprKind::Lambda { params, body } = &args[index].kind
+    {
+        let element = |arg: Option<&Type>| match arg {
+            Some(Type::Vec(element)) => (**element).clone(),
+            _ => Type::Unknown,
+        };
+        let param_types = match name {
+            "map" => vec![element(arg_types.get(1))],
+            "fold" => vec![element(arg_types.get(2)), arg_types[0].clone()],
+            _ => Vec::new(),
+        };
+        arg_types[index] = infer_lambda(params, &param_types, body, sym_table, diagnostics);
+    }
+    arg_types
+}
+
+/// The result of `map` or `fold` given the function it was passed: a
+/// vector of the function's results, or the final accumulator.
+fn callback_return_type(function: &Expr, arg_types: &[Type]) -> Option<Type> {
+    let ExprKind::Identifier(name) = &function.kind else {
+        return None;
+    };
+    let (index, _) = callback_arity(name)?;
+    let Some(Type::Function { return_type, .. }) = arg_types.get(index) else {
+        return None;
+    };
+    match name.as_str() {
+        "map" => Some(Type::Vec(return_type.clone())),
+        "fold" if **return_type != Type::Unknown => Some(*return_type.clone()),
+        "fold" => arg_types.first().cloned(),
+        _ => None,
+    }
+}
+
+/// Checks that a lambda passed to a higher-order builtin such as `map` or
+/// `fold` takes as many parameters as the builtin will supply.
+fn check_callback_arity(function: &Expr, args: &[Expr], diagnostics: &mut Vec<Diagnostic>) {
+    let ExprKind::Identifier(name) = &function.kind else {
+        return;
+    };
+    let Some((index, arity)) = callback_arity(name) else {
+        return;
+    };
+    if let Some(Expr {
+        kind: ExprKind::Lambda { params, .. },
+        range,
+        ..
+    }) = args.get(index)
+        && params.len() != arity
+    {
+        diagnostics.push(Diagnostic {
+            range: *range,
+            severity: Some(DiagnosticSeverity::ERROR),
+            message: format!(
+                "{} expects a {}-argument function, got {}.",
+                name,
+                arity,
+                params.len()
+            ),
+            ..Default::default()
+        });
+    }
+}
+
+/// Checks that the operands of a binary operator have types it accepts.
+///
+/// `&&`/`||` need `Bool`, arithmetic needs numbers, comparisons need two
+/// numbers or two compatible types, and `⊗` needs two gates or two qubits.
+/// `Unknown` operands are never reported.
+fn check_binary_op(
+    op: &BinaryOperator,
+    left: &Type,
+    right: &Type,
+    range: Range,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if matches!(left, Type::Unknown) || matches!(right, Type::Unknown) {
+        return;
+    }
+
+    // Locations and qubits wrap a usize, so arithmetic on them is allowed.
+    let is_numeric = |t: &Type| matches!(t, Type::Int | Type::Float | Type::Location | Type::Qubit);
+    let comparable = types_compatible(left, right) || (is_numeric(left) && is_numeric(right));
+    let valid = match op {
+        BinaryOperator::And | BinaryOperator::Or => *left == Type::Bool && *right == Type::Bool,
+        BinaryOperator::Add
+        | BinaryOperator::Sub
+        | BinaryOperator::Mul
+        | BinaryOperator::Div
+        | BinaryOperator::Mod
+        | BinaryOperator::Pow => is_numeric(left) && is_numeric(right),
+        BinaryOperator::Eq
+        | BinaryOperator::Ne
+        | BinaryOperator::Lt
+        | BinaryOperator::Le
+        | BinaryOperator::Gt
+        | BinaryOperator::Ge => comparable,
+        BinaryOperator::Tensor => tensor_result_type(left, right).is_some(),
+        BinaryOperator::Range => return,
+    };
+
+    if !valid {
+        diagnostics.push(Diagnostic {
+            range,
+            severity: Some(DiagnosticSeverity::ERROR),
+            message: format!("Cannot apply '{}' to {} and {}.", op.symbol(), left, right),
+            ..Default::default()
+        });
+    }
+}
+
+/// Result type of a tensor product, or `None` if the operands can't be
+/// combined: gates compose into a gate and qubits into a qubit map.
+fn tensor_result_type(left: &Type, right: &Type) -> Option<Type> {
+    match (left, right) {
+        (Type::Unknown, _) | (_, Type::Unknown) => Some(Type::Unknown),
+        (Type::Gate, Type::Gate) => Some(Type::Gate),
+        (Type::Qubit, Type::Qubit) => Some(Type::QubitMap),
+        _ => None,
+    }
+}
+
+/// Result type of an arithmetic operation.
+///
+/// `Int` op `Int` stays `Int`; any `Float` operand promotes to `Float`.
+/// Anything else is `Unknown`.
+fn numeric_result_type(left: &Type, right: &Type) -> Type {
+    match (left, right) {
+        (Type::Int, Type::Int) => Type::Int,
+        (Type::Float, Type::Int | Type::Float) | (Type::Int, Type::Float) => Type::Float,
         _ => Type::Unknown,
     }
 }
 
+/// Explains a mismatch that only differs by an `Option` wrapper, such as a
+/// bare `Vec<Location>` where `Option<Vec<Location>>` is expected.
+fn option_mismatch(expected: &Type, actual: &Type) -> Option<String> {
+    match (expected, actual) {
+        (Type::Option(inner), _) if types_compatible(inner, actual) => Some(format!(
+            "Expected {}, got {}; wrap in Some(...).",
+            expected, actual
+        )),
+        (_, Type::Option(inner)) if types_compatible(expected, inner) => Some(format!(
+            "Expected {}, got {}; the value may be None.",
+            expected, actual
+        )),
+        _ => None,
+    }
+}
+
 /// Checks if two types are compatible for assignment or comparison.
 ///
 /// - Treats `Unknown` as compatible with all types to avoid cascading errors
@@ -536,6 +1340,8 @@ fn types_compatible(t1: &Type, t2: &Type) -> bool {
         (Type::ArchT, Type::ArchT) => true,
         (Type::StateT, Type::StateT) => true,
 
+        (Type::Struct { name: n1, .. }, Type::Struct { name: n2, .. }) => n1 == n2,
+
         (Type::Vec(inner1), Type::Vec(inner2)) => types_compatible(inner1, inner2),
         (Type::Tuple(items1), Type::Tuple(items2)) => {
             items1.len() == items2.len()
@@ -550,10 +1356,12 @@ fn types_compatible(t1: &Type, t2: &Type) -> bool {
             Type::Function {
                 params: p1,
                 return_type: r1,
+                variadic: false,
             },
             Type::Function {
                 params: p2,
                 return_type: r2,
+                variadic: false,
             },
         ) => {
             p1.len() == p2.len()
@@ -614,18 +1422,22 @@ mod tests {
         let fn1 = Type::Function {
             params: vec![Type::Int],
             return_type: Box::new(Type::Bool),
+            variadic: false,
         };
         let fn2 = Type::Function {
             params: vec![Type::Float],
             return_type: Box::new(Type::Bool),
+            variadic: false,
         };
         let fn3 = Type::Function {
             params: vec![Type::Int, Type::Int],
             return_type: Box::new(Type::Bool),
+            variadic: false,
         };
         let fn4 = Type::Function {
             params: vec![Type::Int],
             return_type: Box::new(Type::Int),
+            variadic: false,
         };
 
         assert!(types_compatible(&fn1, &fn2));
@@ -30,9 +30,12 @@ pub enum Type {
     Option(Box<Type>),
 
     // Function types
+    /// A variadic function accepts any number of arguments, each checked
+    /// against the first entry of `params` (if any).
     Function {
         params: Vec<Type>,
         return_type: Box<Type>,
+        variadic: bool,
     },
 
     // Struct types
@@ -44,6 +47,170 @@ pub enum Type {
     Unknown,
 }
 
+/// Renders a type in Amaro syntax, e.g. `Vec<Location>` or
+/// `Qubit -> Location`. Struct types print as their name.
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Float => write!(f, "Float"),
+            Type::Bool => write!(f, "Bool"),
+            Type::String => write!(f, "String"),
+            Type::Location => write!(f, "Location"),
+            Type::Qubit => write!(f, "Qubit"),
+            Type::QubitMap => write!(f, "QubitMap"),
+            Type::Gate => write!(f, "Gate"),
+            Type::ArchT => write!(f, "Arch"),
+            Type::StateT => write!(f, "State"),
+            Type::InstrT => write!(f, "Instr"),
+            Type::Vec(inner) => write!(f, "Vec<{}>", inner),
+            Type::Tuple(items) => write!(f, "({})", join_types(items)),
+            Type::Option(inner) => write!(f, "Option<{}>", inner),
+            Type::Function {
+                params,
+                return_type,
+                variadic,
+            } => match params.as_slice() {
+                [param]
+                    if !variadic && !matches!(param, Type::Tuple(_) | Type::Function { .. }) =>
+                {
+                    write!(f, "{} -> {}", param, return_type)
+                }
+                _ => write!(
+                    f,
+                    "({}{}) -> {}",
+                    join_types(params),
+                    if *variadic { "..." } else { "" },
+                    return_type
+                ),
+            },
+            Type::Struct { name, .. } => write!(f, "{}", name),
+            Type::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Renders `types` separated by commas.
+pub fn join_types(types: &[Type]) -> String {
+    types
+        .iter()
+        .map(Type::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Built-in members of each block and collection type, in the order
+/// completion offers them. Their types come from [`member_type`].
+fn builtin_member_names(ty: &Type) -> &'static [&'static str] {
+    match ty {
+        Type::Vec(_) => &["push", "pop", "extend", "is_empty", "contains", "len"],
+        Type::ArchT => &[
+            "width",
+            "height",
+            "stack_size",
+            "edges",
+            "succ_rates",
+            "contains_edge",
+            "magic_state_qubits",
+            "alg_qubits",
+        ],
+        Type::StateT => &["map", "gates", "implemented_gates"],
+        Type::Gate => &[
+            "qubits",
+            "gate_type",
+            "implementation",
+            "x_indices",
+            "y_indices",
+            "z_indices",
+        ],
+        _ => &[],
+    }
+}
+
+/// Returns the type of `object.field`, or `Unknown` if `object` has no such
+/// member.
+pub fn member_type(object: &Type, field: &str) -> Type {
+    let method = |params: Vec<Type>, return_type: Type| Type::Function {
+        params,
+        return_type: Box::new(return_type),
+        variadic: false,
+    };
+
+    match object {
+        Type::Vec(inner) => match field {
+            "push" => method(vec![*inner.clone()], Type::Vec(inner.clone())),
+            "pop" => method(vec![], Type::Option(inner.clone())),
+            "extend" => method(vec![Type::Vec(inner.clone())], Type::Vec(inner.clone())),
+            "is_empty" => method(vec![], Type::Bool),
+            "contains" => method(vec![*inner.clone()], Type::Bool),
+            "len" => method(vec![], Type::Int),
+            _ => Type::Unknown,
+        },
+        Type::Struct { fields, .. } => fields.get(field).cloned().unwrap_or(Type::Unknown),
+        Type::Tuple(elements) => field
+            .parse::<usize>()
+            .ok()
+            .and_then(|idx| elements.get(idx).cloned())
+            .unwrap_or(Type::Unknown),
+
+        // Built-in Types
+        Type::ArchT => match field {
+            "width" | "height" | "stack_size" => Type::Int,
+            "edges" => method(
+                vec![],
+                Type::Vec(Box::new(Type::Tuple(vec![Type::Location, Type::Location]))),
+            ),
+            "succ_rates" => Type::Vec(Box::new(Type::Vec(Box::new(Type::Float)))),
+            "contains_edge" => method(
+                vec![Type::Tuple(vec![Type::Location, Type::Location])],
+                Type::Bool,
+            ),
+            "magic_state_qubits" | "alg_qubits" => {
+                method(vec![], Type::Vec(Box::new(Type::Location)))
+            }
+            _ => Type::Unknown,
+        },
+        Type::StateT => match field {
+            "map" => method(vec![], Type::QubitMap),
+            "gates" => method(vec![], Type::Vec(Box::new(Type::Gate))),
+            _ => Type::Unknown,
+        },
+        Type::Gate => match field {
+            "qubits" => Type::Vec(Box::new(Type::Qubit)),
+            "gate_type" => method(vec![], Type::Gate),
+            "x_indices" | "y_indices" | "z_indices" => {
+                method(vec![], Type::Vec(Box::new(Type::Qubit)))
+            }
+            _ => Type::Unknown,
+        },
+        _ => Type::Unknown,
+    }
+}
+
+/// Lists the members of `object` with their types: built-in members, struct
+/// fields sorted by name, or tuple indices.
+pub fn members(object: &Type) -> Vec<(String, Type)> {
+    match object {
+        Type::Struct { fields, .. } => {
+            let mut fields: Vec<_> = fields
+                .iter()
+                .map(|(name, ty)| (name.clone(), ty.clone()))
+                .collect();
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+            fields
+        }
+        Type::Tuple(elements) => elements
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| (i.to_string(), ty.clone()))
+            .collect(),
+        _ => builtin_member_names(object)
+            .iter()
+            .map(|name| (name.to_string(), member_type(object, name)))
+            .collect(),
+    }
+}
+
 /// A scoped symbol table for tracking variable bindings and their types.
 ///
 /// Uses a stack of scopes to support nested let-bindings and lambda parameters.
@@ -51,22 +218,112 @@ pub enum Type {
 pub struct SymbolTable {
     // bindings: HashMap<String, Type>,
     scopes: Vec<HashMap<String, Type>>,
+    /// Names bound later in each enclosing let chain, which are in the
+    /// source but not yet in scope.
+    pending: Vec<Vec<String>>,
+    /// One-line descriptions of the built-in functions and constructors.
+    docs: HashMap<String, &'static str>,
+    /// The block being checked, when built by [`SymbolTable::for_block`].
+    block_kind: Option<String>,
+}
+
+/// Context variables that only make sense in some `*Info` blocks, with the
+/// blocks they are available in. Other block kinds see all of them.
+const BLOCK_CONTEXT_VARS: [(&str, &[&str]); 4] = [
+    ("Gate", &["RouteInfo"]),
+    ("State", &["RouteInfo", "TransitionInfo", "StateInfo"]),
+    ("Transition", &["TransitionInfo"]),
+    ("step", &["TransitionInfo", "StateInfo"]),
+];
+
+const INFO_BLOCKS: [&str; 4] = ["RouteInfo", "TransitionInfo", "ArchInfo", "StateInfo"];
+
+/// Returns true if the context variable `name` may be used in a block of
+/// kind `block_kind`.
+fn context_var_available(name: &str, block_kind: &str) -> bool {
+    if !INFO_BLOCKS.contains(&block_kind) {
+        return true;
+    }
+    BLOCK_CONTEXT_VARS
+        .iter()
+        .find(|(var, _)| *var == name)
+        .is_none_or(|(_, blocks)| blocks.contains(&block_kind))
+}
+
+/// Higher-order builtins, with the index of their function argument and
+/// the number of parameters that function must take. Kept in step with
+/// [`SymbolTable::register_builtin_functions`].
+const CALLBACK_ARITIES: [(&str, usize, usize); 2] = [("map", 0, 1), ("fold", 1, 2)];
+
+/// For a higher-order builtin, returns the index of its function argument
+/// and how many parameters that function takes.
+pub fn callback_arity(name: &str) -> Option<(usize, usize)> {
+    CALLBACK_ARITIES
+        .iter()
+        .find(|(builtin, _, _)| *builtin == name)
+        .map(|&(_, index, arity)| (index, arity))
 }
 
 impl SymbolTable {
     /// Creates a new symbol table with all built-in types and functions registered.
     pub fn new() -> Self {
         let mut global_scope = HashMap::new();
+        let mut docs = HashMap::new();
 
         Self::register_context_vars(&mut global_scope);
-        Self::register_constructors(&mut global_scope);
+        Self::register_constructors(&mut global_scope, &mut docs);
         Self::register_gate_literals(&mut global_scope);
-        Self::register_builtin_functions(&mut global_scope);
+        Self::register_builtin_functions(&mut global_scope, &mut docs);
         SymbolTable {
             scopes: vec![global_scope],
+            pending: Vec::new(),
+            docs,
+            block_kind: None,
         }
     }
 
+    /// Creates a symbol table for checking a block of kind `block_kind`.
+    ///
+    /// The block-scoped context variables (`Gate`, `State`, `Transition`,
+    /// `step`) move out of the global scope into a context scope holding only
+    /// the ones available in that block.
+    pub fn for_block(block_kind: &str) -> Self {
+        let mut table = Self::new();
+        let mut context = HashMap::new();
+        for (name, _) in BLOCK_CONTEXT_VARS {
+            if let Some(ty) = table.scopes[0].remove(name)
+                && context_var_available(name, block_kind)
+            {
+                context.insert(name.to_string(), ty);
+            }
+        }
+        table.scopes.push(context);
+        table.block_kind = Some(block_kind.to_string());
+        table
+    }
+
+    /// Returns the block kind if `name` is a context variable that is not
+    /// available in the block this table was built for.
+    pub fn unavailable_context(&self, name: &str) -> Option<&str> {
+        let kind = self.block_kind.as_deref()?;
+        let is_context_var = BLOCK_CONTEXT_VARS.iter().any(|(var, _)| *var == name);
+        (is_context_var && !context_var_available(name, kind)).then_some(kind)
+    }
+
+    /// Returns the built-in helper functions (map, fold, all_paths, ...) with their types.
+    pub fn builtin_functions() -> HashMap<String, Type> {
+        let mut scope = HashMap::new();
+        Self::register_builtin_functions(&mut scope, &mut HashMap::new());
+        scope
+    }
+
+    /// Returns the gate literals (CX, T, Pauli, ...) with their types.
+    pub fn gate_literals() -> HashMap<String, Type> {
+        let mut scope = HashMap::new();
+        Self::register_gate_literals(&mut scope);
+        scope
+    }
+
     /// Enters a new scope for let-bindings or lambda parameters.
     pub fn enter_scope(&mut self) {
         self.scopes.push(HashMap::new());
@@ -96,6 +353,33 @@ impl SymbolTable {
         None
     }
 
+    /// Returns the description of a built-in function or constructor.
+    pub fn doc(&self, name: &str) -> Option<&'static str> {
+        self.docs.get(name).copied()
+    }
+
+    /// Returns every name visible from the current scope.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.scopes
+            .iter()
+            .flat_map(|scope| scope.keys().map(String::as_str))
+    }
+
+    /// Records names that a let chain binds after the value being checked.
+    pub fn push_pending(&mut self, names: Vec<String>) {
+        self.pending.push(names);
+    }
+
+    /// Forgets the names recorded by the matching `push_pending`.
+    pub fn pop_pending(&mut self) {
+        self.pending.pop();
+    }
+
+    /// Returns true if `name` is bound later in an enclosing let chain.
+    pub fn is_pending(&self, name: &str) -> bool {
+        self.pending.iter().flatten().any(|n| n == name)
+    }
+
     /// Registers context variables (Arch, State, Gate, Transition, etc.).
     fn register_context_vars(scope: &mut HashMap<String, Type>) {
         scope.insert("Arch".to_string(), Type::ArchT);
@@ -120,26 +404,44 @@ impl SymbolTable {
     }
 
     /// Registers type constructors (Location, Qubit, Vec).
-    fn register_constructors(scope: &mut HashMap<String, Type>) {
+    fn register_constructors(
+        scope: &mut HashMap<String, Type>,
+        docs: &mut HashMap<String, &'static str>,
+    ) {
+        docs.insert(
+            "Qubit".to_string(),
+            "Builds a logical qubit from its index.",
+        );
         scope.insert(
             "Qubit".to_string(),
             Type::Function {
                 params: vec![Type::Int],
                 return_type: Box::new(Type::Qubit),
+                variadic: false,
             },
         );
+        docs.insert(
+            "Location".to_string(),
+            "Builds a physical location from its index in the architecture.",
+        );
         scope.insert(
             "Location".to_string(),
             Type::Function {
                 params: vec![Type::Int],
                 return_type: Box::new(Type::Location),
+                variadic: false,
             },
         );
+        docs.insert(
+            "Vec".to_string(),
+            "Builds a vector from its arguments; `Vec()` is empty.",
+        );
         scope.insert(
             "Vec".to_string(),
             Type::Function {
                 params: vec![],
                 return_type: Box::new(Type::Vec(Box::new(Type::Unknown))),
+                variadic: true,
             },
         );
     }
@@ -159,50 +461,82 @@ impl SymbolTable {
             "S",
             "Sdg",
             "Tdg",
-            "RX",
-            "RY",
-            "RZ",
         ] {
             scope.insert(gate.to_string(), Type::Gate);
         }
+        // Rotation gates need an angle before they are a gate
+        for gate in ["RX", "RY", "RZ"] {
+            scope.insert(
+                gate.to_string(),
+                Type::Function {
+                    params: vec![Type::Float],
+                    return_type: Box::new(Type::Gate),
+                    variadic: false,
+                },
+            );
+        }
     }
 
     /// Registers built-in helper functions (map, fold, all_paths, steiner_trees, etc.).
-    fn register_builtin_functions(scope: &mut HashMap<String, Type>) {
+    fn register_builtin_functions(
+        scope: &mut HashMap<String, Type>,
+        docs: &mut HashMap<String, &'static str>,
+    ) {
         // Quantum map operations
+        docs.insert(
+            "value_swap".to_string(),
+            "Swaps the qubits mapped to two locations, returning the updated map.",
+        );
         scope.insert(
             "value_swap".to_string(),
             Type::Function {
                 params: vec![Type::Location, Type::Location],
                 return_type: Box::new(Type::QubitMap),
+                variadic: false,
             },
         );
 
+        docs.insert(
+            "values".to_string(),
+            "Returns the locations a qubit map assigns to its qubits.",
+        );
         scope.insert(
             "values".to_string(),
             Type::Function {
                 params: vec![Type::QubitMap],
                 return_type: Box::new(Type::Vec(Box::new(Type::Location))),
+                variadic: false,
             },
         );
 
+        docs.insert(
+            "identity_application".to_string(),
+            "Applies a transition without changing the qubit map.",
+        );
         scope.insert(
             "identity_application".to_string(),
             Type::Function {
                 params: vec![Type::Unknown],
                 return_type: Box::new(Type::Unknown),
+                variadic: false,
             },
         );
 
         // Higher-order
+        docs.insert(
+            "map".to_string(),
+            "Applies a function to every element of a vector.",
+        );
         scope.insert(
             "map".to_string(),
             Type::Function {
                 params: vec![Type::Unknown, Type::Vec(Box::new(Type::Unknown))],
                 return_type: Box::new(Type::Vec(Box::new(Type::Unknown))),
+                variadic: false,
             },
         );
 
+        docs.insert("fold".to_string(), "Combines the elements of a vector into one value, starting from an initial accumulator.");
         scope.insert(
             "fold".to_string(),
             Type::Function {
@@ -212,40 +546,62 @@ impl SymbolTable {
                     Type::Vec(Box::new(Type::Unknown)),
                 ],
                 return_type: Box::new(Type::Unknown),
+                variadic: false,
             },
         );
 
         // Neighbor functions
+        docs.insert("vertical_neighbors".to_string(), "Returns the locations directly above and below a location, given the grid width and height.");
         scope.insert(
             "vertical_neighbors".to_string(),
             Type::Function {
                 params: vec![Type::Location, Type::Int, Type::Int],
                 return_type: Box::new(Type::Vec(Box::new(Type::Location))),
+                variadic: false,
             },
         );
+        docs.insert(
+            "horizontal_neighbors".to_string(),
+            "Returns the locations directly left and right of a location, given the grid width.",
+        );
         scope.insert(
             "horizontal_neighbors".to_string(),
             Type::Function {
                 params: vec![Type::Location, Type::Int],
                 return_type: Box::new(Type::Vec(Box::new(Type::Location))),
+                variadic: false,
             },
         );
 
         // Path functions
+        docs.insert(
+            "path".to_string(),
+            "Returns the locations along the current realization's path.",
+        );
         scope.insert(
             "path".to_string(),
             Type::Function {
                 params: vec![],
                 return_type: Box::new(Type::Vec(Box::new(Type::Location))),
+                variadic: false,
             },
         );
+        docs.insert(
+            "tree".to_string(),
+            "Returns the locations in the current realization's Steiner tree.",
+        );
         scope.insert(
             "tree".to_string(),
             Type::Function {
                 params: vec![],
                 return_type: Box::new(Type::Vec(Box::new(Type::Location))),
+                variadic: false,
             },
         );
+        docs.insert(
+            "all_paths".to_string(),
+            "Enumerates all paths in the architecture between the given endpoint sets.",
+        );
         scope.insert(
             "all_paths".to_string(),
             Type::Function {
@@ -256,8 +612,13 @@ impl SymbolTable {
                     Type::Vec(Box::new(Type::Location)),
                 ],
                 return_type: Box::new(Type::Vec(Box::new(Type::Vec(Box::new(Type::Location))))),
+                variadic: false,
             },
         );
+        docs.insert(
+            "shortest_path".to_string(),
+            "Finds a shortest path between the given endpoint sets, or `None` if there is none.",
+        );
         scope.insert(
             "shortest_path".to_string(),
             Type::Function {
@@ -268,8 +629,13 @@ impl SymbolTable {
                     Type::Vec(Box::new(Type::Location)),
                 ],
                 return_type: Box::new(Type::Option(Box::new(Type::Vec(Box::new(Type::Location))))),
+                variadic: false,
             },
         );
+        docs.insert(
+            "steiner_trees".to_string(),
+            "Enumerates Steiner trees connecting the given terminal sets in the architecture.",
+        );
         scope.insert(
             "steiner_trees".to_string(),
             Type::Function {
@@ -278,7 +644,88 @@ impl SymbolTable {
                     Type::Vec(Box::new(Type::Vec(Box::new(Type::Location)))),
                     Type::Vec(Box::new(Type::Location)),
                 ],
-                return_type: Box::new(Type::Vec(Box::new(Type::Location))),
+                return_type: Box::new(Type::Vec(Box::new(Type::Vec(Box::new(Type::Location))))),
+                variadic: false,
+            },
+        );
+
+        // Numeric helpers
+        docs.insert(
+            "abs".to_string(),
+            "Returns the absolute value of an integer.",
+        );
+        scope.insert(
+            "abs".to_string(),
+            Type::Function {
+                params: vec![Type::Int],
+                return_type: Box::new(Type::Int),
+                variadic: false,
+            },
+        );
+        for (name, doc) in [
+            ("max", "Returns the largest of its arguments."),
+            ("min", "Returns the smallest of its arguments."),
+        ] {
+            docs.insert(name.to_string(), doc);
+            scope.insert(
+                name.to_string(),
+                Type::Function {
+                    params: vec![Type::Float],
+                    return_type: Box::new(Type::Float),
+                    variadic: true,
+                },
+            );
+        }
+        docs.insert(
+            "dist".to_string(),
+            "Returns the distance between two locations.",
+        );
+        scope.insert(
+            "dist".to_string(),
+            Type::Function {
+                params: vec![Type::Location, Type::Location],
+                return_type: Box::new(Type::Int),
+                variadic: false,
+            },
+        );
+
+        // Collection helpers
+        docs.insert(
+            "combinations".to_string(),
+            "Returns every combination of the given size from a vector.",
+        );
+        scope.insert(
+            "combinations".to_string(),
+            Type::Function {
+                params: vec![Type::Vec(Box::new(Type::Unknown)), Type::Int],
+                return_type: Box::new(Type::Vec(Box::new(Type::Vec(Box::new(Type::Unknown))))),
+                variadic: false,
+            },
+        );
+        docs.insert(
+            "to_2d".to_string(),
+            "Reshapes a vector into a vector of rows.",
+        );
+        scope.insert(
+            "to_2d".to_string(),
+            Type::Function {
+                params: vec![Type::Vec(Box::new(Type::Unknown))],
+                return_type: Box::new(Type::Vec(Box::new(Type::Vec(Box::new(Type::Unknown))))),
+                variadic: false,
+            },
+        );
+
+        // Mapping checks
+        docs.insert(
+            "consistent".to_string(),
+            "Checks that a path is consistent with the qubit map.",
+        );
+        scope.insert(
+            "consistent".to_string(),
+            Type::Function {
+                params: vec![Type::Vec(Box::new(Type::Location)), Type::QubitMap],
+                return_type: Box::new(Type::Bool),
+                variadic: false,
             },
         );
     }
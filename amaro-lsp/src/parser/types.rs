@@ -0,0 +1,44 @@
+use nom::{IResult, combinator::map};
+
+use super::core::parse_type_annotation;
+use super::symbols::Type;
+use crate::ast::TypeAnnotation;
+
+/// Parses a type such as `Int`, `Vec<Location>`, `(Location, Location)` or
+/// `Option<Qubit>` into a `Type`.
+pub fn parse_type(input: &str) -> IResult<&str, Type> {
+    map(parse_type_annotation, |annotation| {
+        annotation_type(&annotation)
+    })(input)
+}
+
+/// Resolves a written type annotation; unrecognized names are `Unknown`.
+pub fn annotation_type(annotation: &TypeAnnotation) -> Type {
+    match annotation {
+        TypeAnnotation::Simple(name) => match name.as_str() {
+            "Int" => Type::Int,
+            "Float" => Type::Float,
+            "Bool" => Type::Bool,
+            "String" => Type::String,
+            "Location" => Type::Location,
+            "Qubit" => Type::Qubit,
+            "QubitMap" => Type::QubitMap,
+            "Gate" => Type::Gate,
+            _ => Type::Unknown,
+        },
+        TypeAnnotation::Generic(name, args) => match (name.as_str(), args.as_slice()) {
+            ("Vec", [inner]) => Type::Vec(Box::new(annotation_type(inner))),
+            ("Option", [inner]) => Type::Option(Box::new(annotation_type(inner))),
+            _ => Type::Unknown,
+        },
+        TypeAnnotation::Tuple(items) => Type::Tuple(items.iter().map(annotation_type).collect()),
+        TypeAnnotation::Function {
+            params,
+            return_type,
+        } => Type::Function {
+            params: params.iter().map(annotation_type).collect(),
+            return_type: Box::new(annotation_type(return_type)),
+            variadic: false,
+        },
+    }
+}
@@ -1,4 +1,4 @@
-use tower_lsp::lsp_types::{Position, Range};
+use tower_lsp::lsp_types::{Position, Range, TextDocumentContentChangeEvent};
 
 pub fn calc_range(full_text: &str, start_offset: usize, length: usize) -> Range {
     let abs_start = start_offset;
@@ -19,12 +19,120 @@ pub fn calc_range(full_text: &str, start_offset: usize, length: usize) -> Range
     }
 }
 
+/// Converts a byte offset into an LSP `(line, character)` pair.
+///
+/// Columns are counted in UTF-16 code units, as required by the LSP spec.
 pub fn byte_to_position(text: &str, byte_idx: usize) -> (u32, u32) {
-    let safe_idx = std::cmp::min(byte_idx, text.len());
+    let mut safe_idx = std::cmp::min(byte_idx, text.len());
+    while !text.is_char_boundary(safe_idx) {
+        safe_idx -= 1;
+    }
     let slice = &text[..safe_idx];
 
     let line = slice.matches('\n').count() as u32;
     let last_line_start = slice.rfind('\n').map(|i| i + 1).unwrap_or(0);
-    let col = (safe_idx - last_line_start) as u32;
+    // A `\r\n` pair terminates the line; the `\r` is not a column.
+    let line_text = slice[last_line_start..].trim_end_matches('\r');
+    let col = line_text.chars().map(|c| c.len_utf16() as u32).sum();
     (line, col)
 }
+
+/// Converts an LSP position (UTF-16 columns) back into a byte offset.
+pub fn position_to_byte(text: &str, pos: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..pos.line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
+        }
+    }
+
+    let mut line_end = text[line_start..]
+        .find('\n')
+        .map(|i| line_start + i)
+        .unwrap_or(text.len());
+    if text[..line_end].ends_with('\r') {
+        line_end -= 1;
+    }
+
+    let mut units = 0;
+    for (i, c) in text[line_start..line_end].char_indices() {
+        if units >= pos.character as usize {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    line_end
+}
+
+/// Applies an incremental change to a document buffer.
+///
+/// A change without a range replaces the whole document.
+pub fn apply_change(text: &mut String, change: &TextDocumentContentChangeEvent) {
+    match change.range {
+        Some(range) => {
+            let start = position_to_byte(text, range.start);
+            let end = std::cmp::max(start, position_to_byte(text, range.end));
+            text.replace_range(start..end, &change.text);
+        }
+        None => *text = change.text.clone(),
+    }
+}
+
+/// Returns the byte span of the identifier touching `byte_idx`, if any.
+pub fn identifier_at(text: &str, byte_idx: usize) -> Option<(usize, usize)> {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let idx = std::cmp::min(byte_idx, text.len());
+
+    let start = text[..idx]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_ident(c))
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(idx);
+    let end = text[idx..]
+        .char_indices()
+        .find(|&(_, c)| !is_ident(c))
+        .map(|(i, _)| idx + i)
+        .unwrap_or(text.len());
+
+    if start == end || text[start..].starts_with(|c: char| c.is_ascii_digit()) {
+        None
+    } else {
+        Some((start, end))
+    }
+}
+
+/// Levenshtein distance between `a` and `b`, counted in chars.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+
+    prev[b.len()]
+}
+
+/// Returns the candidate closest to `name` within `max_distance` edits,
+/// preferring the alphabetically first on ties.
+pub fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+    max_distance: usize,
+) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .filter(|c| *c != name)
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(d, _)| *d <= max_distance)
+        .min()
+        .map(|(_, c)| c)
+}
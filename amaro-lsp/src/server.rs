@@ -1,17 +1,203 @@
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::RwLock;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
 use crate::ast::*;
-use crate::parser::{check_semantics, parse_file};
+use crate::features::code_actions::code_actions;
+use crate::features::completion::completions_at;
+use crate::features::diagnostics::{cross_file_duplicates, diagnostic_report};
+use crate::features::embedded::rust_regions;
+use crate::features::folding::folding_ranges;
+use crate::features::formatting::format_document;
+use crate::features::hover::hover_at;
+use crate::features::inlay_hints::inlay_hints;
+use crate::features::navigation::{
+    definition_at, document_highlights_at, prepare_rename_at, references_at, rename_at,
+};
+use crate::features::selection_range::selection_ranges;
+use crate::features::semantic_tokens::{legend, semantic_tokens};
+use crate::features::signature_help::signature_help_at;
+use crate::features::workspace_symbols::{block_symbol_kind, workspace_symbols};
+use crate::parser::config::CONFIGURABLE_RULES;
+use crate::parser::utils::apply_change;
+use crate::parser::{AnalysisConfig, RuleSeverity, diagnose_parsed, parse_file_with_config};
 
 #[derive(Debug)]
 pub struct Backend {
     pub client: Client,
-    pub documents: Arc<RwLock<HashMap<Url, String>>>,
+    pub documents: Arc<RwLock<HashMap<Url, Document>>>,
+    pub config: Arc<RwLock<AnalysisConfig>>,
+    /// Log every parsed AST to the client; see `InitializationOptions::debug_ast`.
+    pub debug_ast: AtomicBool,
+}
+
+/// An open document and the most recent parse of its text.
+///
+/// Feature handlers read `parsed` instead of reparsing on every request.
+#[derive(Debug)]
+pub struct Document {
+    pub text: String,
+    pub parsed: std::result::Result<AmaroFile, ParseError>,
+    /// Diagnostics from analysing this document on its own.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Diagnostics that depend on the other open documents, such as a block
+    /// kind defined in more than one file.
+    pub workspace_diagnostics: Vec<Diagnostic>,
+}
+
+/// Command that returns the pretty-printed AST of an open document; its one
+/// argument is the document URI.
+pub const DUMP_AST_COMMAND: &str = "amaro.dumpAst";
+
+/// Parameters of the `amaro/rustRegions` request.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustRegionsParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+/// Options read from `InitializeParams.initialization_options`.
+#[derive(Debug, Default)]
+pub struct InitializationOptions {
+    /// `"knownBlocks": ["GateRealization", "MyDialectInfo", ...]` replaces
+    /// the built-in block kinds. Omit it to keep the defaults.
+    pub known_blocks: Option<Vec<String>>,
+    /// `"debugAst": true` logs the full parsed AST on every change at
+    /// `MessageType::LOG`. Off by default since the dump is large.
+    pub debug_ast: bool,
+    /// `"maxFileBytes": 4194304` raises or lowers the size above which a
+    /// file only gets block-level checks. Defaults to 1 MiB.
+    pub max_file_bytes: Option<usize>,
+    /// `"severities": { "capitalization": "error", "unusedLet": "off" }`
+    /// overrides the severity of individual lint rules; `"off"` silences
+    /// a rule. See `CONFIGURABLE_RULES` for the rule names; unknown rules
+    /// and severities are skipped with a warning.
+    pub severities: HashMap<String, RuleSeverity>,
+    /// `"requireMandatoryBlocks": false` stops reporting missing
+    /// `RouteInfo`/`TransitionInfo` blocks, for libraries of fragments.
+    pub require_mandatory_blocks: Option<bool>,
+    /// `"fragmentGlob": "*.frag.qmrl"` skips the mandatory-block check for
+    /// matching file names only.
+    pub fragment_glob: Option<String>,
+    /// One message per option that was ignored because it is unknown or
+    /// malformed; `initialize` logs them as warnings.
+    pub warnings: Vec<String>,
+}
+
+impl InitializationOptions {
+    /// Reads the options key by key. A malformed or unknown key is skipped
+    /// with a warning and keeps its default; the other keys still apply.
+    pub fn from_value(value: Option<serde_json::Value>) -> Self {
+        let mut options = InitializationOptions::default();
+        let map = match value {
+            None | Some(serde_json::Value::Null) => return options,
+            Some(serde_json::Value::Object(map)) => map,
+            Some(other) => {
+                options.warnings.push(format!(
+                    "Ignoring initialization options: expected an object, got {}.",
+                    other
+                ));
+                return options;
+            }
+        };
+
+        for (key, value) in map {
+            let result = match key.as_str() {
+                "knownBlocks" => read_option(value, &mut options.known_blocks),
+                "debugAst" => read_option(value, &mut options.debug_ast),
+                "maxFileBytes" => read_option(value, &mut options.max_file_bytes),
+                "severities" => {
+                    options.read_severities(value);
+                    continue;
+                }
+                "requireMandatoryBlocks" => {
+                    read_option(value, &mut options.require_mandatory_blocks)
+                }
+                "fragmentGlob" => read_option(value, &mut options.fragment_glob),
+                _ => {
+                    options
+                        .warnings
+                        .push(format!("Ignoring unknown initialization option '{}'.", key));
+                    continue;
+                }
+            };
+            if let Err(e) = result {
+                options
+                    .warnings
+                    .push(format!("Ignoring initialization option '{}': {}.", key, e));
+            }
+        }
+        options
+    }
+
+    /// Reads `"severities"` one rule at a time, so an unknown rule name or
+    /// severity only drops that entry.
+    fn read_severities(&mut self, value: serde_json::Value) {
+        let serde_json::Value::Object(map) = value else {
+            self.warnings.push(format!(
+                "Ignoring initialization option 'severities': expected an object, got {}.",
+                value
+            ));
+            return;
+        };
+
+        for (rule, severity) in map {
+            if !CONFIGURABLE_RULES.contains(&rule.as_str()) {
+                self.warnings.push(format!(
+                    "Ignoring severity for unknown rule '{}'. Expected one of: {}.",
+                    rule,
+                    CONFIGURABLE_RULES.join(", ")
+                ));
+                continue;
+            }
+            match serde_json::from_value::<RuleSeverity>(severity) {
+                Ok(severity) => {
+                    self.severities.insert(rule, severity);
+                }
+                Err(e) => self
+                    .warnings
+                    .push(format!("Ignoring severity for rule '{}': {}.", rule, e)),
+            }
+        }
+    }
+
+    pub fn into_config(self) -> AnalysisConfig {
+        let mut config = AnalysisConfig::default();
+        if let Some(known_blocks) = self.known_blocks {
+            config.known_blocks = known_blocks;
+        }
+        if let Some(max_file_bytes) = self.max_file_bytes {
+            config.max_file_bytes = max_file_bytes;
+        }
+        config.severities = self.severities;
+        if let Some(require_mandatory_blocks) = self.require_mandatory_blocks {
+            config.require_mandatory_blocks = require_mandatory_blocks;
+        }
+        config.fragment_glob = self.fragment_glob;
+        config
+    }
+}
+
+/// Deserializes `value` into `slot`, leaving `slot` untouched on failure.
+fn read_option<T: serde::de::DeserializeOwned>(
+    value: serde_json::Value,
+    slot: &mut T,
+) -> serde_json::Result<()> {
+    *slot = serde_json::from_value(value)?;
+    Ok(())
+}
+
+/// Renders a parse result for the `amaro.dumpAst` command.
+pub fn dump_ast(parsed: &std::result::Result<AmaroFile, ParseError>) -> String {
+    match parsed {
+        Ok(file) => format!("{:#?}", file),
+        Err(e) => format!("Parse error: {}", e),
+    }
 }
 
 // Symbol Tree Builder
@@ -19,12 +205,7 @@ pub fn build_document_symbols(file: &AmaroFile) -> Vec<DocumentSymbol> {
     file.blocks
         .iter()
         .map(|block| {
-            let kind = match block.kind.as_str() {
-                "GateRealization" | "Transition" | "Architecture" | "Arch" => SymbolKind::CLASS,
-                "Step" => SymbolKind::FUNCTION,
-                "RouteInfo" | "TransitionInfo" | "ArchInfo" | "StateInfo" => SymbolKind::MODULE,
-                _ => SymbolKind::OBJECT,
-            };
+            let kind = block_symbol_kind(&block.kind);
 
             #[allow(deprecated)]
             let children: Vec<DocumentSymbol> = match &block.content {
@@ -302,7 +483,9 @@ fn summarize_expr_detailed(expr: &Expr, depth: usize) -> String {
             }
         }
 
-        ExprKind::LetBinding { name, value, body } => {
+        ExprKind::LetBinding {
+            name, value, body, ..
+        } => {
             if depth == 0 {
                 format!(
                     "let {} = {}\n      in {}",
@@ -348,52 +531,162 @@ impl Backend {
         Backend {
             client,
             documents: Arc::new(RwLock::new(HashMap::new())),
+            config: Arc::new(RwLock::new(AnalysisConfig::default())),
+            debug_ast: AtomicBool::new(false),
         }
     }
 
     // Validating Document
-    pub async fn validate_document(&self, uri: Url, text: String) {
-        let mut diagnostics = Vec::new();
-
-        // Syntactic Analysis
-        match parse_file(&text) {
-            Ok(file) => {
-                // Semantic Checks
-                // #[cfg(debug_assertions)]
-                // {
-                //     let ast_summary = format_simple_ast(&file);
-                //     self.client.log_message(MessageType::INFO, format!("Parsed AST:\n{}", ast_summary)).await;
-                // }
-
-                let mut semantic_errors = check_semantics(&file);
-                diagnostics.append(&mut semantic_errors);
-            }
-            Err(e) => {
-                diagnostics.push(Diagnostic {
-                    range: Range::default(),
-                    severity: Some(DiagnosticSeverity::ERROR),
-                    message: format!("Fatal Syntax Error: Parsing aborted.\nParse error: {}", e),
-                    ..Default::default()
-                });
-            }
+    /// Rebuilds the cached document for `uri` from `edit`, then publishes
+    /// its diagnostics. `edit` receives the previous text (empty if the
+    /// document wasn't open) and returns the new one.
+    ///
+    /// The write lock is held while reparsing so that concurrent edits are
+    /// applied in order and the cache never goes back to an older text.
+    pub async fn update_document(&self, uri: Url, edit: impl FnOnce(String) -> String) {
+        let config = self.config.read().await.for_document(&uri);
+
+        let ast_dump = {
+            let mut docs = self.documents.write().await;
+            let text = edit(docs.remove(&uri).map(|doc| doc.text).unwrap_or_default());
+            let parsed = parse_file_with_config(&text, &config);
+
+            let diagnostics = diagnose_parsed(&parsed, &config);
+            let ast_dump = match &parsed {
+                Ok(file) if self.debug_ast.load(Ordering::Relaxed) => {
+                    Some(format!("Parsed AST:\n{:#?}", file))
+                }
+                _ => None,
+            };
+
+            docs.insert(
+                uri.clone(),
+                Document {
+                    text,
+                    parsed,
+                    diagnostics,
+                    workspace_diagnostics: Vec::new(),
+                },
+            );
+            ast_dump
+        };
+
+        if let Some(ast_dump) = ast_dump {
+            self.client.log_message(MessageType::LOG, ast_dump).await;
         }
 
-        self.client
-            .publish_diagnostics(uri, diagnostics, Some(1))
-            .await;
+        self.publish_workspace_diagnostics(Some(uri)).await;
+    }
+
+    /// Recomputes the cross-file diagnostics of every open document and
+    /// publishes those that changed, plus `changed` itself.
+    async fn publish_workspace_diagnostics(&self, changed: Option<Url>) {
+        let to_publish: Vec<(Url, Vec<Diagnostic>)> = {
+            let mut docs = self.documents.write().await;
+            let files: Vec<_> = docs
+                .iter()
+                .filter_map(|(uri, doc)| doc.parsed.as_ref().ok().map(|file| (uri, file)))
+                .collect();
+            let mut duplicates = cross_file_duplicates(&files);
+
+            docs.iter_mut()
+                .filter_map(|(uri, doc)| {
+                    let workspace_diagnostics = duplicates.remove(uri).unwrap_or_default();
+                    if changed.as_ref() != Some(uri)
+                        && workspace_diagnostics == doc.workspace_diagnostics
+                    {
+                        return None;
+                    }
+                    doc.workspace_diagnostics = workspace_diagnostics;
+                    let mut all = doc.diagnostics.clone();
+                    all.extend(doc.workspace_diagnostics.iter().cloned());
+                    Some((uri.clone(), all))
+                })
+                .collect()
+        };
+
+        for (uri, diagnostics) in to_publish {
+            self.client
+                .publish_diagnostics(uri, diagnostics, Some(1))
+                .await;
+        }
+    }
+
+    /// Handles the custom `amaro/rustRegions` request: the ranges of the
+    /// embedded Rust blocks in an open document.
+    pub async fn rust_regions(&self, params: RustRegionsParams) -> Result<Vec<Range>> {
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(Vec::new()),
+        };
+
+        match &doc.parsed {
+            Ok(file) => Ok(rust_regions(file)),
+            Err(_) => Ok(Vec::new()),
+        }
     }
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        let mut options = InitializationOptions::from_value(params.initialization_options);
+        for warning in std::mem::take(&mut options.warnings) {
+            self.client.log_message(MessageType::WARNING, warning).await;
+        }
+        self.debug_ast.store(options.debug_ast, Ordering::Relaxed);
+        *self.config.write().await = options.into_config();
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::FULL,
+                    TextDocumentSyncKind::INCREMENTAL,
                 )),
 
                 document_symbol_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                completion_provider: Some(CompletionOptions::default()),
+                signature_help_provider: Some(SignatureHelpOptions {
+                    trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+                    retrigger_characters: None,
+                    work_done_progress_options: Default::default(),
+                }),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                })),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: legend(),
+                            full: Some(SemanticTokensFullOptions::Bool(true)),
+                            range: None,
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![DUMP_AST_COMMAND.to_string()],
+                    work_done_progress_options: Default::default(),
+                }),
+                diagnostic_provider: Some(DiagnosticServerCapabilities::Options(
+                    DiagnosticOptions {
+                        identifier: Some("amaro".to_string()),
+                        inter_file_dependencies: false,
+                        workspace_diagnostics: false,
+                        ..Default::default()
+                    },
+                )),
 
                 ..Default::default()
             },
@@ -411,29 +704,22 @@ impl LanguageServer for Backend {
         let uri = params.text_document.uri.clone();
         let text = params.text_document.text.clone();
 
-        self.documents
-            .write()
-            .await
-            .insert(uri.clone(), text.clone());
-
         // self.client
         //     .log_message(MessageType::INFO, "Amaro file opened!")
         //     .await;
-        self.validate_document(uri, text).await;
+        self.update_document(uri, |_| text).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri.clone();
 
-        if let Some(change) = params.content_changes.into_iter().next() {
-            let text = change.text.clone();
-            self.documents
-                .write()
-                .await
-                .insert(uri.clone(), text.clone());
-
-            self.validate_document(uri, text).await;
-        }
+        self.update_document(uri, |mut text| {
+            for change in &params.content_changes {
+                apply_change(&mut text, change);
+            }
+            text
+        })
+        .await;
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
@@ -444,6 +730,7 @@ impl LanguageServer for Backend {
         self.client
             .publish_diagnostics(params.text_document.uri, vec![], Some(1))
             .await;
+        self.publish_workspace_diagnostics(None).await;
     }
 
     async fn shutdown(&self) -> Result<()> {
@@ -455,16 +742,337 @@ impl LanguageServer for Backend {
         params: DocumentSymbolParams,
     ) -> Result<Option<DocumentSymbolResponse>> {
         let docs = self.documents.read().await;
-        let text = match docs.get(&params.text_document.uri) {
-            Some(t) => t,
+        let doc = match docs.get(&params.text_document.uri) {
+            Some(d) => d,
             None => return Ok(None),
         };
 
-        if let Ok(file) = parse_file(text) {
-            let symbols = build_document_symbols(&file);
+        if let Ok(file) = &doc.parsed {
+            let symbols = build_document_symbols(file);
             return Ok(Some(DocumentSymbolResponse::Nested(symbols)));
         }
 
         Ok(None)
     }
+
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>> {
+        let docs = self.documents.read().await;
+        let files: Vec<_> = docs
+            .iter()
+            .filter_map(|(uri, doc)| doc.parsed.as_ref().ok().map(|file| (uri, file)))
+            .collect();
+
+        Ok(Some(workspace_symbols(&files, &params.query)))
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let docs = self.documents.read().await;
+        let position_params = params.text_document_position_params;
+        let doc = match docs.get(&position_params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        Ok(doc
+            .parsed
+            .as_ref()
+            .ok()
+            .and_then(|file| hover_at(file, position_params.position)))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let position_params = params.text_document_position;
+        let config = self
+            .config
+            .read()
+            .await
+            .for_document(&position_params.text_document.uri);
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&position_params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        let items = completions_at(
+            &doc.text,
+            doc.parsed.as_ref().ok(),
+            position_params.position,
+            &config,
+        );
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn signature_help(&self, params: SignatureHelpParams) -> Result<Option<SignatureHelp>> {
+        let docs = self.documents.read().await;
+        let position_params = params.text_document_position_params;
+        let doc = match docs.get(&position_params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        Ok(signature_help_at(
+            &doc.text,
+            doc.parsed.as_ref().ok(),
+            position_params.position,
+        ))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = &params.text_document.uri;
+        let config = self.config.read().await.for_document(uri);
+        let docs = self.documents.read().await;
+        let doc = match docs.get(uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            let actions = code_actions(
+                uri,
+                &doc.text,
+                file,
+                params.range,
+                &params.context.diagnostics,
+                &config,
+            );
+            return Ok(Some(actions));
+        }
+
+        Ok(None)
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let docs = self.documents.read().await;
+        let position_params = params.text_document_position_params;
+        let uri = &position_params.text_document.uri;
+        let doc = match docs.get(uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            return Ok(definition_at(uri, file, position_params.position)
+                .map(GotoDefinitionResponse::Scalar));
+        }
+
+        Ok(None)
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let docs = self.documents.read().await;
+        let position_params = params.text_document_position;
+        let uri = &position_params.text_document.uri;
+        let doc = match docs.get(uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            return Ok(Some(references_at(
+                uri,
+                file,
+                position_params.position,
+                params.context.include_declaration,
+            )));
+        }
+
+        Ok(Some(Vec::new()))
+    }
+
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> Result<Option<Vec<DocumentHighlight>>> {
+        let docs = self.documents.read().await;
+        let position_params = params.text_document_position_params;
+        let doc = match docs.get(&position_params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            return Ok(Some(document_highlights_at(file, position_params.position)));
+        }
+
+        Ok(None)
+    }
+
+    async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> Result<DocumentDiagnosticReportResult> {
+        let config = self
+            .config
+            .read()
+            .await
+            .for_document(&params.text_document.uri);
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&params.text_document.uri) {
+            Some(d) => d,
+            None => {
+                return Ok(DocumentDiagnosticReportResult::Report(
+                    DocumentDiagnosticReport::Full(Default::default()),
+                ));
+            }
+        };
+
+        let report = diagnostic_report(
+            &doc.text,
+            &doc.parsed,
+            &config,
+            params.previous_result_id.as_deref(),
+        );
+        Ok(DocumentDiagnosticReportResult::Report(report))
+    }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            return Ok(Some(folding_ranges(file)));
+        }
+
+        Ok(None)
+    }
+
+    async fn selection_range(
+        &self,
+        params: SelectionRangeParams,
+    ) -> Result<Option<Vec<SelectionRange>>> {
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            return Ok(Some(selection_ranges(file, &params.positions)));
+        }
+
+        Ok(None)
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            return Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+                result_id: None,
+                data: semantic_tokens(&doc.text, file),
+            })));
+        }
+
+        Ok(None)
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            return Ok(Some(inlay_hints(file, params.range)));
+        }
+
+        Ok(None)
+    }
+
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            return Ok(prepare_rename_at(file, params.position).map(PrepareRenameResponse::Range));
+        }
+
+        Ok(None)
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let docs = self.documents.read().await;
+        let position_params = params.text_document_position;
+        let uri = &position_params.text_document.uri;
+        let doc = match docs.get(uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            return rename_at(uri, file, position_params.position, &params.new_name)
+                .map(Some)
+                .map_err(tower_lsp::jsonrpc::Error::invalid_params);
+        }
+
+        Ok(None)
+    }
+
+    async fn execute_command(
+        &self,
+        params: ExecuteCommandParams,
+    ) -> Result<Option<serde_json::Value>> {
+        if params.command != DUMP_AST_COMMAND {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!(
+                "Unknown command '{}'",
+                params.command
+            )));
+        }
+
+        let uri = params
+            .arguments
+            .into_iter()
+            .next()
+            .and_then(|arg| serde_json::from_value::<Url>(arg).ok())
+            .ok_or_else(|| {
+                tower_lsp::jsonrpc::Error::invalid_params(format!(
+                    "'{}' expects a document URI",
+                    DUMP_AST_COMMAND
+                ))
+            })?;
+
+        let docs = self.documents.read().await;
+        let doc = docs.get(&uri).ok_or_else(|| {
+            tower_lsp::jsonrpc::Error::invalid_params(format!("Document is not open: {}", uri))
+        })?;
+
+        Ok(Some(serde_json::Value::String(dump_ast(&doc.parsed))))
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&params.text_document.uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        if let Ok(file) = &doc.parsed {
+            return Ok(Some(format_document(&doc.text, file)));
+        }
+
+        Ok(None)
+    }
 }
@@ -0,0 +1,154 @@
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use serde_json::json;
+
+fn write_temp(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("amaro-lint-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn lint(args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_amaro-lint"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_lint_reports_errors_and_fails() {
+    let path = write_temp("missing.qmrl", "Architecture[name='test']\n");
+    let path = path.to_str().unwrap();
+
+    let (code, stdout) = lint(&[path]);
+    assert_eq!(code, 1);
+    assert_eq!(
+        stdout,
+        format!(
+            "{p}:1:1: error: Missing mandatory block: 'RouteInfo'.\n\
+             {p}:1:1: error: Missing mandatory block: 'TransitionInfo'.\n",
+            p = path
+        )
+    );
+}
+
+#[test]
+fn test_lint_clean_file_succeeds_and_json_format() {
+    let clean = write_temp("clean.qmrl", include_str!("../../examples/ilq.qmrl"));
+    let (code, stdout) = lint(&["--format", "json", clean.to_str().unwrap()]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout.trim(), "[]");
+
+    let warn = write_temp(
+        "warn.qmrl",
+        "routeinfo:\n    routed_gates = CX\n    realize_gate = []\n\
+         TransitionInfo:\n    cost = 1.0\n    apply = []\n    get_transitions = []\n",
+    );
+    let (code, stdout) = lint(&["--format", "json", warn.to_str().unwrap()]);
+    assert_eq!(code, 0, "Warnings alone should not fail: {}", stdout);
+
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let entries = json.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["severity"], "warning");
+    assert_eq!(entries[0]["line"], 1);
+    assert_eq!(entries[0]["path"], warn.to_str().unwrap());
+}
+
+/// Writes one LSP message with its `Content-Length` header.
+fn send(stdin: &mut impl Write, message: serde_json::Value) {
+    let body = message.to_string();
+    write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    stdin.flush().unwrap();
+}
+
+/// Reads one LSP message, or `None` once the server's output ends.
+fn receive(stdout: &mut impl BufRead) -> Option<serde_json::Value> {
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if stdout.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length: ") {
+            length = value.parse().ok()?;
+        }
+    }
+    let mut body = vec![0; length];
+    stdout.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+#[test]
+fn test_server_binary_uses_library_diagnostics() {
+    let source = "routeinfo:\n    routed_gates = CX\n    realize_gate = 1 + 'a'\nStateInfo:\n";
+    let uri = "file:///server-binary.qmrl";
+
+    let mut server = Command::new(env!("CARGO_BIN_EXE_amaro-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = server.stdin.take().unwrap();
+    let stdout = server.stdout.take().unwrap();
+
+    // Read on a thread so a misbehaving server fails the test instead of hanging it
+    let (sender, messages) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut stdout = BufReader::new(stdout);
+        while let Some(message) = receive(&mut stdout) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    send(
+        &mut stdin,
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"capabilities": {}}}),
+    );
+    // Like a real client, wait for the server to finish initializing
+    let next = || {
+        messages
+            .recv_timeout(Duration::from_secs(10))
+            .expect("Server stopped responding")
+    };
+    while next()["id"] != 1 {}
+
+    send(
+        &mut stdin,
+        json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+    );
+    send(
+        &mut stdin,
+        json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+            "textDocument": {"uri": uri, "languageId": "amaro", "version": 1, "text": source}
+        }}),
+    );
+
+    let published = loop {
+        let message = next();
+        if message["method"] == "textDocument/publishDiagnostics" && message["params"]["uri"] == uri
+        {
+            break message["params"]["diagnostics"].clone();
+        }
+    };
+    let _ = server.kill();
+    let _ = server.wait();
+
+    let expected = serde_json::to_value(amaro_lsp::diagnose(source)).unwrap();
+    assert!(!expected.as_array().unwrap().is_empty());
+    assert_eq!(published, expected);
+}
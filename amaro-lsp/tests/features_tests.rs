@@ -0,0 +1,1037 @@
+use amaro_lsp::features::code_actions::code_actions;
+use amaro_lsp::features::completion::completions_at;
+use amaro_lsp::features::diagnostics::{cross_file_duplicates, diagnostic_report};
+use amaro_lsp::features::embedded::rust_regions;
+use amaro_lsp::features::folding::folding_ranges;
+use amaro_lsp::features::formatting::{format_document, format_text};
+use amaro_lsp::features::hover::hover_at;
+use amaro_lsp::features::inlay_hints::inlay_hints;
+use amaro_lsp::features::navigation::{
+    definition_at, document_highlights_at, prepare_rename_at, references_at, rename_at,
+};
+use amaro_lsp::features::selection_range::selection_ranges;
+use amaro_lsp::features::semantic_tokens::{TOKEN_TYPES, semantic_tokens};
+use amaro_lsp::features::signature_help::signature_help_at;
+use amaro_lsp::features::workspace_symbols::workspace_symbols;
+use amaro_lsp::parser::config::rule_code;
+use amaro_lsp::parser::utils::position_to_byte;
+use amaro_lsp::parser::{AnalysisConfig, check_semantics, check_semantics_with_config, parse_file};
+use amaro_lsp::server::{build_document_symbols, dump_ast};
+use tower_lsp::lsp_types::{
+    CodeActionKind, CodeActionOrCommand, CompletionItemKind, DocumentDiagnosticReport,
+    DocumentHighlightKind, HoverContents, InlayHintLabel, InsertTextFormat, Position, Range,
+    SemanticTokenType, SignatureHelp, SymbolKind, TextEdit, Url,
+};
+
+fn hover_text(text: &str, line: u32, character: u32) -> Option<String> {
+    let file = parse_file(text).unwrap();
+    hover_at(&file, Position { line, character }).map(|hover| match hover.contents {
+        HoverContents::Markup(markup) => markup.value,
+        other => panic!("Expected markup hover, got: {:?}", other),
+    })
+}
+
+// Hover Tests
+
+#[test]
+fn test_hover_builtin_function_signature() {
+    let input = "RouteInfo:\n    realize_gate = map(|x| -> x, [1])";
+    let text = hover_text(input, 1, 20).expect("Should hover 'map'");
+    assert!(
+        text.contains("map(Unknown, Vec<Unknown>) -> Vec<Unknown>"),
+        "Got: {}",
+        text
+    );
+}
+
+#[test]
+fn test_hover_builtin_description() {
+    let input = "RouteInfo:\n    realize_gate = all_paths(Arch, [], [], [])";
+    let text = hover_text(input, 1, 22).expect("Should hover 'all_paths'");
+    assert!(
+        text.ends_with(
+            "```\n\nEnumerates all paths in the architecture between the given endpoint sets."
+        ),
+        "Got: {}",
+        text
+    );
+}
+
+#[test]
+fn test_hover_block_keyword() {
+    let input = "TransitionInfo:\n    cost = 0.0";
+    let text = hover_text(input, 0, 3).expect("Should hover block keyword");
+    assert!(text.contains("TransitionInfo"));
+    assert!(text.contains("block"));
+}
+
+#[test]
+fn test_hover_range_covers_identifier() {
+    let input = "RouteInfo:\n    x = value_swap(a, b)";
+    let file = parse_file(input).unwrap();
+    let hover = hover_at(
+        &file,
+        Position {
+            line: 1,
+            character: 10,
+        },
+    )
+    .unwrap();
+    let range = hover.range.unwrap();
+    assert_eq!(range.start.character, 8);
+    assert_eq!(range.end.character, 18);
+}
+
+#[test]
+fn test_hover_nothing_found() {
+    let input = "RouteInfo:\n    x = unknown_thing";
+    assert!(hover_text(input, 1, 10).is_none());
+    // Whitespace between tokens
+    assert!(hover_text(input, 1, 1).is_none());
+}
+
+// Completion Tests
+
+#[test]
+fn test_completion_offers_blocks_functions_and_gates() {
+    let input = "RouteInfo:\n    realize_gate = ";
+    let items = completions_at(
+        input,
+        None,
+        Position {
+            line: 1,
+            character: 19,
+        },
+        &AnalysisConfig::default(),
+    );
+
+    let find = |label: &str| items.iter().find(|i| i.label == label);
+
+    let block = find("TransitionInfo").expect("Should offer block kinds");
+    assert_eq!(block.kind, Some(CompletionItemKind::CLASS));
+
+    let func = find("horizontal_neighbors").expect("Should offer builtins");
+    assert_eq!(func.kind, Some(CompletionItemKind::FUNCTION));
+    assert_eq!(
+        func.detail.as_deref(),
+        Some("horizontal_neighbors(Location, Int) -> Vec<Location>")
+    );
+    assert_eq!(
+        func.insert_text.as_deref(),
+        Some("horizontal_neighbors(${1:Location}, ${2:Int})")
+    );
+    assert_eq!(func.insert_text_format, Some(InsertTextFormat::SNIPPET));
+
+    let gate = find("CX").expect("Should offer gate literals");
+    assert_eq!(gate.kind, Some(CompletionItemKind::CONSTANT));
+}
+
+fn member_labels(input: &str, line: u32, character: u32) -> Vec<String> {
+    let file = parse_file(input).ok();
+    completions_at(
+        input,
+        file.as_ref(),
+        Position { line, character },
+        &AnalysisConfig::default(),
+    )
+    .into_iter()
+    .map(|item| item.label)
+    .collect()
+}
+
+#[test]
+fn test_completion_offers_members_after_dot() {
+    let input = "RouteInfo:\n    realize_gate = State.ma";
+    assert_eq!(
+        member_labels(input, 1, 27),
+        vec!["map", "gates", "implemented_gates"]
+    );
+
+    let input = "RouteInfo:\n    realize_gate = (values(State.map())).";
+    let items = completions_at(
+        input,
+        None,
+        Position::new(1, 41),
+        &AnalysisConfig::default(),
+    );
+    let labels: Vec<_> = items.iter().map(|i| i.label.as_str()).collect();
+    assert_eq!(
+        labels,
+        vec!["push", "pop", "extend", "is_empty", "contains", "len"]
+    );
+    assert_eq!(items[1].kind, Some(CompletionItemKind::METHOD));
+    assert_eq!(
+        items[1].detail.as_deref(),
+        Some("pop() -> Option<Location>")
+    );
+
+    let input = "RouteInfo:\n    realize_gate = Arch.width";
+    let items = completions_at(
+        input,
+        None,
+        Position::new(1, 24),
+        &AnalysisConfig::default(),
+    );
+    let width = items.iter().find(|i| i.label == "width").unwrap();
+    assert_eq!(width.kind, Some(CompletionItemKind::FIELD));
+}
+
+#[test]
+fn test_completion_members_of_file_structs() {
+    let input = "TransitionInfo:\n    Transition{edge : (Location, Location)}\n    apply = Transition.edge.\n";
+    assert_eq!(member_labels(input, 2, 23), vec!["edge"]);
+    assert_eq!(member_labels(input, 2, 28), vec!["0", "1"]);
+
+    // Nothing to offer for values of unknown type
+    let input = "StateInfo:\n    cost = foo(1).";
+    assert!(member_labels(input, 1, 18).is_empty());
+}
+
+// Document Symbol Tests
+
+#[test]
+fn test_document_symbols_outline_blocks() {
+    let input = r#"RouteInfo:
+    routed_gates = CX
+    GateRealization{u : Location}
+
+Architecture[
+    width = 10
+]"#;
+    let file = parse_file(input).unwrap();
+    let symbols = build_document_symbols(&file);
+
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(symbols[0].name, "RouteInfo");
+    assert_eq!(symbols[0].range, file.blocks[0].range);
+    assert_eq!(symbols[0].selection_range, file.blocks[0].range);
+    assert_eq!(symbols[1].name, "Architecture");
+    assert_eq!(symbols[1].kind, SymbolKind::CLASS);
+
+    let children = symbols[0].children.as_ref().expect("Fields as children");
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[0].name, "routed_gates");
+    assert_eq!(children[0].kind, SymbolKind::FIELD);
+    assert_eq!(children[1].name, "GateRealization");
+    assert_eq!(children[1].kind, SymbolKind::STRUCT);
+}
+
+#[test]
+fn test_document_symbols_empty_block_has_no_children() {
+    let file = parse_file("StateInfo:\n").unwrap();
+    let symbols = build_document_symbols(&file);
+    assert_eq!(symbols.len(), 1);
+    assert!(symbols[0].children.is_none());
+}
+
+// Code Action Tests
+
+fn test_uri() -> Url {
+    Url::parse("file:///test.qmrl").unwrap()
+}
+
+fn action_edits(action: &CodeActionOrCommand) -> Vec<TextEdit> {
+    let CodeActionOrCommand::CodeAction(action) = action else {
+        panic!("Expected code action, got command");
+    };
+    let changes = action.edit.as_ref().unwrap().changes.as_ref().unwrap();
+    changes[&test_uri()].clone()
+}
+
+/// Drops the refactors offered alongside the quick fixes.
+fn quick_fixes(actions: Vec<CodeActionOrCommand>) -> Vec<CodeActionOrCommand> {
+    actions
+        .into_iter()
+        .filter(|action| {
+            matches!(action, CodeActionOrCommand::CodeAction(a)
+                if a.kind == Some(CodeActionKind::QUICKFIX))
+        })
+        .collect()
+}
+
+#[test]
+fn test_capitalization_quick_fix() {
+    let input = "RouteInfo:\n    routed_gates = CX\narchitecture[name='test']";
+    let file = parse_file(input).unwrap();
+    let diags: Vec<_> = check_semantics(&file)
+        .into_iter()
+        .filter(|d| d.code == rule_code("capitalization"))
+        .collect();
+    let cursor = Range {
+        start: Position {
+            line: 2,
+            character: 3,
+        },
+        end: Position {
+            line: 2,
+            character: 3,
+        },
+    };
+
+    let actions = quick_fixes(code_actions(
+        &test_uri(),
+        input,
+        &file,
+        cursor,
+        &diags,
+        &AnalysisConfig::default(),
+    ));
+    assert_eq!(actions.len(), 1);
+
+    let edits = action_edits(&actions[0]);
+    assert_eq!(edits.len(), 1);
+    assert_eq!(edits[0].new_text, "Architecture");
+    assert_eq!(edits[0].range, file.blocks[1].range);
+    assert_eq!(edits[0].range.end.character, 12);
+
+    let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+        unreachable!()
+    };
+    assert_eq!(action.kind, Some(CodeActionKind::QUICKFIX));
+    assert_eq!(action.diagnostics.as_ref().map(|d| d.len()), Some(1));
+}
+
+#[test]
+fn test_no_capitalization_fix_away_from_block() {
+    let input = "RouteInfo:\n    routed_gates = CX\narchitecture[name='test']";
+    let file = parse_file(input).unwrap();
+    let cursor = Range {
+        start: Position {
+            line: 0,
+            character: 0,
+        },
+        end: Position {
+            line: 0,
+            character: 0,
+        },
+    };
+    let actions = code_actions(
+        &test_uri(),
+        input,
+        &file,
+        cursor,
+        &[],
+        &AnalysisConfig::default(),
+    );
+    assert!(actions.is_empty());
+}
+
+#[test]
+fn test_capitalization_fix_and_completion_follow_known_blocks() {
+    let config = AnalysisConfig {
+        known_blocks: vec!["RouteInfo".to_string(), "Layout".to_string()],
+        ..Default::default()
+    };
+    let input = "RouteInfo:\n    routed_gates = CX\nlayout[name='test']";
+    let file = parse_file(input).unwrap();
+    let diags: Vec<_> = check_semantics_with_config(&file, &config)
+        .into_iter()
+        .filter(|d| d.code == rule_code("capitalization"))
+        .collect();
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+
+    let cursor = Range::new(Position::new(2, 3), Position::new(2, 3));
+    let actions = quick_fixes(code_actions(
+        &test_uri(),
+        input,
+        &file,
+        cursor,
+        &diags,
+        &config,
+    ));
+    assert_eq!(actions.len(), 1);
+    assert_eq!(action_edits(&actions[0])[0].new_text, "Layout");
+
+    let blocks: Vec<_> = completions_at(input, Some(&file), Position::new(1, 4), &config)
+        .into_iter()
+        .filter(|item| item.kind == Some(CompletionItemKind::CLASS))
+        .map(|item| item.label)
+        .collect();
+    assert_eq!(blocks, vec!["RouteInfo", "Layout"]);
+}
+
+#[test]
+fn test_missing_block_quick_fixes() {
+    let input = "Architecture[name='test']";
+    let file = parse_file(input).unwrap();
+    let diags = check_semantics(&file);
+
+    let actions = quick_fixes(code_actions(
+        &test_uri(),
+        input,
+        &file,
+        Range::default(),
+        &diags,
+        &AnalysisConfig::default(),
+    ));
+    assert_eq!(actions.len(), 2, "One action per missing block");
+
+    let titles: Vec<_> = actions
+        .iter()
+        .map(|a| match a {
+            CodeActionOrCommand::CodeAction(a) => a.title.clone(),
+            _ => panic!("Expected code action"),
+        })
+        .collect();
+    assert!(titles.iter().any(|t| t.contains("RouteInfo")));
+    assert!(titles.iter().any(|t| t.contains("TransitionInfo")));
+
+    let edits = action_edits(&actions[0]);
+    assert_eq!(
+        edits[0].new_text,
+        "\n\nRouteInfo:\n    routed_gates = \n    realize_gate = \n"
+    );
+    // Inserted at the end of the document
+    assert_eq!(edits[0].range.start.character, 25);
+    assert_eq!(edits[0].range.start, edits[0].range.end);
+}
+
+#[test]
+fn test_missing_block_fix_result_parses() {
+    let input = "RouteInfo:\n    routed_gates = CX\n    realize_gate = []\n";
+    let file = parse_file(input).unwrap();
+    let diags = check_semantics(&file);
+
+    let actions = code_actions(
+        &test_uri(),
+        input,
+        &file,
+        Range::default(),
+        &diags,
+        &AnalysisConfig::default(),
+    );
+    assert_eq!(actions.len(), 1);
+
+    let fixed = format!("{}{}", input, action_edits(&actions[0])[0].new_text);
+    let fixed_file = parse_file(&fixed).unwrap();
+    assert_eq!(fixed_file.blocks.len(), 2);
+    assert_eq!(fixed_file.blocks[1].kind, "TransitionInfo");
+}
+
+fn convert_to_colon(input: &str, cursor: Position) -> Option<String> {
+    let file = parse_file(input).unwrap();
+    let range = Range::new(cursor, cursor);
+    let action = code_actions(
+        &test_uri(),
+        input,
+        &file,
+        range,
+        &[],
+        &AnalysisConfig::default(),
+    )
+    .into_iter()
+    .find(|action| {
+        matches!(action, CodeActionOrCommand::CodeAction(a)
+                if a.kind == Some(CodeActionKind::REFACTOR_REWRITE))
+    })?;
+    let edit = &action_edits(&action)[0];
+
+    let start = position_to_byte(input, edit.range.start);
+    let end = position_to_byte(input, edit.range.end);
+    Some(format!(
+        "{}{}{}",
+        &input[..start],
+        edit.new_text,
+        &input[end..]
+    ))
+}
+
+#[test]
+fn test_convert_bracket_block_to_colon_block() {
+    let input = "GateRealization[\n        routed_gates = CX\n\n        data = map(|x| -> x,\n            Vec())\n]\nArchitecture[name='test'] // arch\n";
+    assert_eq!(
+        convert_to_colon(input, Position::new(0, 3)).unwrap(),
+        "GateRealization:\n    routed_gates = CX\n\n    data = map(|x| -> x,\n        Vec())\nArchitecture[name='test'] // arch\n"
+    );
+
+    // Trailing text after ']' moves to its own line
+    let converted = convert_to_colon(input, Position::new(6, 2)).unwrap();
+    assert!(
+        converted.ends_with("Architecture:\n    name='test'\n // arch\n"),
+        "{}",
+        converted
+    );
+
+    assert_eq!(
+        convert_to_colon("Architecture[]", Position::new(0, 0)).unwrap(),
+        "Architecture:"
+    );
+    assert!(convert_to_colon("StateInfo:\n    cost = 1.0\n", Position::new(0, 0)).is_none());
+}
+
+// Formatting Tests
+
+fn format(input: &str) -> String {
+    let file = parse_file(input).unwrap();
+    format_text(input, &file)
+}
+
+#[test]
+fn test_format_indents_colon_block_fields() {
+    let input = "RouteInfo:\n  routed_gates = CX\n        realize_gate = []\n";
+    assert_eq!(
+        format(input),
+        "RouteInfo:\n    routed_gates = CX\n    realize_gate = []\n"
+    );
+}
+
+#[test]
+fn test_format_keeps_continuation_indentation_relative() {
+    let input = "RouteInfo:\n  realize_gate = if a\n      then b\n      else c\n";
+    assert_eq!(
+        format(input),
+        "RouteInfo:\n    realize_gate = if a\n        then b\n        else c\n"
+    );
+}
+
+#[test]
+fn test_format_collapses_blank_lines_between_blocks() {
+    let input = "RouteInfo:\n    routed_gates = CX\n\n\n\nTransitionInfo:\n    cost = 1.0\nStateInfo:\n    cost = 1.0\n\n\n";
+    assert_eq!(
+        format(input),
+        "RouteInfo:\n    routed_gates = CX\n\nTransitionInfo:\n    cost = 1.0\n\nStateInfo:\n    cost = 1.0\n"
+    );
+}
+
+#[test]
+fn test_format_strips_trailing_whitespace_and_keeps_comments() {
+    let input = "// Header   \nRouteInfo:   \n    routed_gates = CX   \n\n// Next\nGateRealization[\n  name = 'x'  \n]\n";
+    assert_eq!(
+        format(input),
+        "// Header\nRouteInfo:\n    routed_gates = CX\n\n// Next\nGateRealization[\n  name = 'x'\n]\n"
+    );
+}
+
+#[test]
+fn test_format_header_after_block_on_same_line() {
+    let input = "Fo[n='ééé'] RouteInfo:\n    routed_gates = CX\n";
+    assert_eq!(format(input), input);
+}
+
+#[test]
+fn test_format_bracket_in_string_does_not_end_block() {
+    // The interior, blank lines included, is kept verbatim up to the real `]`.
+    let input = "GateRealization[\n  name = ']'  // ]\n\n\n  other = 1\n]\n";
+    assert_eq!(format(input), input);
+}
+
+#[test]
+fn test_format_is_idempotent_on_examples() {
+    let input = include_str!("../../examples/nisq.qmrl");
+    let once = format(input);
+    assert_eq!(format(&once), once);
+
+    let file = parse_file(&once).unwrap();
+    assert!(format_document(&once, &file).is_empty());
+}
+
+#[test]
+fn test_hover_variadic_signature() {
+    let input = "StateInfo:\n    cost = Vec(1, 2)";
+    let text = hover_text(input, 1, 12).expect("Should hover 'Vec'");
+    assert!(text.contains("Vec(...) -> Vec<Unknown>"), "Got: {}", text);
+}
+
+// Navigation Tests
+
+#[test]
+fn test_definition_jumps_to_block() {
+    let input =
+        "GateRealization[\n    name = 'g'\n]\nRouteInfo:\n    realize_gate = GateRealization\n";
+    let file = parse_file(input).unwrap();
+    let uri = Url::parse("file:///test.qmrl").unwrap();
+
+    let location = definition_at(
+        &uri,
+        &file,
+        Position {
+            line: 4,
+            character: 22,
+        },
+    )
+    .expect("Should resolve 'GateRealization'");
+    assert_eq!(location.uri, uri);
+    assert_eq!(location.range, file.blocks[0].range);
+}
+
+#[test]
+fn test_definition_none_for_non_block() {
+    let input = "RouteInfo:\n    realize_gate = value_swap\n";
+    let file = parse_file(input).unwrap();
+    let uri = Url::parse("file:///test.qmrl").unwrap();
+
+    let position = Position {
+        line: 1,
+        character: 22,
+    };
+    assert!(definition_at(&uri, &file, position).is_none());
+}
+
+#[test]
+fn test_references_to_block() {
+    let input = "GateRealization[\n    name = 'g'\n]\nRouteInfo:\n    realize_gate = [GateRealization, other]\n    routed_gates = (GateRealization)\n";
+    let file = parse_file(input).unwrap();
+    let uri = Url::parse("file:///test.qmrl").unwrap();
+    let position = Position {
+        line: 0,
+        character: 3,
+    };
+
+    let refs = references_at(&uri, &file, position, false);
+    assert_eq!(refs.len(), 2, "Got: {:?}", refs);
+    assert_eq!(refs[0].range.start, Position::new(4, 20));
+    assert_eq!(refs[0].range.end, Position::new(4, 35));
+    assert_eq!(refs[1].range.start.line, 5);
+
+    let with_decl = references_at(&uri, &file, position, true);
+    assert_eq!(with_decl.len(), 3);
+    assert_eq!(with_decl[0].range, file.blocks[0].range);
+}
+
+#[test]
+fn test_references_empty_when_unused() {
+    let input = "GateRealization[\n    name = 'g'\n]\n";
+    let file = parse_file(input).unwrap();
+    let uri = Url::parse("file:///test.qmrl").unwrap();
+
+    let refs = references_at(&uri, &file, Position::new(0, 3), false);
+    assert!(refs.is_empty());
+}
+
+// Document Highlight Tests
+
+#[test]
+fn test_document_highlight_let_binding() {
+    let input = "StateInfo:\n    cost = let d = 2 in d * d\n";
+    let file = parse_file(input).unwrap();
+
+    let highlights = document_highlights_at(&file, Position::new(1, 28));
+    let found: Vec<_> = highlights
+        .iter()
+        .map(|h| (h.range.start.character, h.range.end.character, h.kind))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (15, 16, Some(DocumentHighlightKind::WRITE)),
+            (24, 25, Some(DocumentHighlightKind::READ)),
+            (28, 29, Some(DocumentHighlightKind::READ)),
+        ]
+    );
+}
+
+#[test]
+fn test_document_highlight_block_name() {
+    let input =
+        "GateRealization[\n    name = 'g'\n]\nRouteInfo:\n    realize_gate = GateRealization\n";
+    let file = parse_file(input).unwrap();
+
+    let highlights = document_highlights_at(&file, Position::new(4, 22));
+    assert_eq!(highlights.len(), 2, "Got: {:?}", highlights);
+    assert_eq!(highlights[0].range, file.blocks[0].range);
+    assert_eq!(highlights[0].kind, Some(DocumentHighlightKind::WRITE));
+    assert_eq!(highlights[1].range.start, Position::new(4, 19));
+    assert_eq!(highlights[1].range.end, Position::new(4, 34));
+    assert_eq!(highlights[1].kind, Some(DocumentHighlightKind::READ));
+
+    assert!(document_highlights_at(&file, Position::new(1, 12)).is_empty());
+}
+
+// Selection Range Tests
+
+fn selection_chain(text: &str, position: Position) -> Vec<Range> {
+    let file = parse_file(text).unwrap();
+    let mut ranges = Vec::new();
+    let mut current = selection_ranges(&file, &[position]).pop();
+    while let Some(selection) = current {
+        ranges.push(selection.range);
+        current = selection.parent.map(|parent| *parent);
+    }
+    ranges
+}
+
+#[test]
+fn test_selection_range_expands_from_identifier_to_block() {
+    let input = "StateInfo:\n    cost = 1 + foo(bar)\n    other = 2\n";
+    let range = |start: u32, end: u32| Range::new(Position::new(1, start), Position::new(1, end));
+
+    assert_eq!(
+        selection_chain(input, Position::new(1, 20)),
+        vec![
+            range(19, 22),
+            range(15, 23),
+            range(11, 23),
+            range(4, 23),
+            Range::new(Position::new(0, 0), Position::new(2, 13)),
+        ]
+    );
+}
+
+#[test]
+fn test_selection_range_outside_blocks_is_empty() {
+    let input = "\n\nStateInfo:\n    cost = 1\n";
+    let chain = selection_chain(input, Position::new(0, 0));
+    assert_eq!(
+        chain,
+        vec![Range::new(Position::new(0, 0), Position::new(0, 0))]
+    );
+}
+
+// Pull Diagnostics Tests
+
+#[test]
+fn test_diagnostic_report_full_then_unchanged() {
+    let input = "StateInfo:\n    cost = missing_name\n";
+    let parsed = parse_file(input);
+    let config = AnalysisConfig::default();
+
+    let DocumentDiagnosticReport::Full(full) = diagnostic_report(input, &parsed, &config, None)
+    else {
+        panic!("First request should return a full report");
+    };
+    let report = full.full_document_diagnostic_report;
+    assert!(
+        report
+            .items
+            .iter()
+            .any(|d| d.message.contains("Undefined variable 'missing_name'"))
+    );
+    let result_id = report
+        .result_id
+        .expect("Full report should carry a result id");
+
+    let unchanged = diagnostic_report(input, &parsed, &config, Some(&result_id));
+    assert!(matches!(unchanged, DocumentDiagnosticReport::Unchanged(_)));
+
+    let edited = "StateInfo:\n    cost = 1.0\n";
+    let edited_report = diagnostic_report(edited, &parse_file(edited), &config, Some(&result_id));
+    assert!(matches!(edited_report, DocumentDiagnosticReport::Full(_)));
+}
+
+#[test]
+fn test_cross_file_duplicate_blocks() {
+    let a_uri = Url::parse("file:///project/a.qmrl").unwrap();
+    let b_uri = Url::parse("file:///project/b.qmrl").unwrap();
+    let c_uri = Url::parse("file:///project/c.qmrl").unwrap();
+    let a = parse_file("RouteInfo:\n    routed_gates = CX\n").unwrap();
+    let b = parse_file("StateInfo:\n    cost = 1.0\n\nrouteinfo:\n    routed_gates = T\n").unwrap();
+    let c = parse_file("ArchInfo:\n    Arch{width : Int}\n").unwrap();
+
+    let result = cross_file_duplicates(&[(&a_uri, &a), (&b_uri, &b), (&c_uri, &c)]);
+    assert!(!result.contains_key(&c_uri));
+
+    let a_diags = &result[&a_uri];
+    assert_eq!(a_diags.len(), 1);
+    assert_eq!(
+        a_diags[0].message,
+        "Block 'RouteInfo' is also defined in b.qmrl."
+    );
+    let related = a_diags[0].related_information.as_ref().unwrap();
+    assert_eq!(related.len(), 1);
+    assert_eq!(related[0].location.uri, b_uri);
+    assert_eq!(related[0].location.range.start, Position::new(3, 0));
+
+    let b_diags = &result[&b_uri];
+    assert_eq!(b_diags.len(), 1);
+    assert_eq!(b_diags[0].range.start, Position::new(3, 0));
+    assert_eq!(
+        b_diags[0].related_information.as_ref().unwrap()[0]
+            .location
+            .uri,
+        a_uri
+    );
+}
+
+// Workspace Symbol Tests
+
+#[test]
+fn test_workspace_symbols_search_all_documents() {
+    let a_uri = Url::parse("file:///w/a.qmrl").unwrap();
+    let b_uri = Url::parse("file:///w/b.qmrl").unwrap();
+    let a = parse_file("RouteInfo:\n    routed_gates = CX\n\nTransitionInfo:\n    cost = 1.0\n")
+        .unwrap();
+    let b = parse_file("ArchInfo:\n    Arch{width : Int}\n\nTransition[name='t']\n").unwrap();
+    let files = [(&a_uri, &a), (&b_uri, &b)];
+
+    let names = |query: &str| -> Vec<(String, String)> {
+        workspace_symbols(&files, query)
+            .into_iter()
+            .map(|s| (s.name, s.location.uri.path().to_string()))
+            .collect()
+    };
+
+    assert_eq!(
+        names("trans"),
+        vec![
+            ("Transition".to_string(), "/w/b.qmrl".to_string()),
+            ("TransitionInfo".to_string(), "/w/a.qmrl".to_string()),
+        ]
+    );
+    // Fuzzy: the query's letters in order, ignoring case
+    assert_eq!(
+        names("trinf"),
+        vec![("TransitionInfo".to_string(), "/w/a.qmrl".to_string())]
+    );
+    assert_eq!(names("").len(), 4);
+    assert!(names("gate").is_empty());
+
+    let symbols = workspace_symbols(&files, "arch");
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].kind, SymbolKind::MODULE);
+    assert_eq!(symbols[0].location.range.start, Position::new(0, 0));
+}
+
+// Folding Tests
+
+#[test]
+fn test_folding_ranges_per_block() {
+    let input = "RouteInfo:\n    routed_gates = CX\n    realize_gate = []\n\nArch[name = 'a']\nGateRealization[\n    name = 'g'\n]\n";
+    let file = parse_file(input).unwrap();
+
+    let ranges = folding_ranges(&file);
+    let lines: Vec<_> = ranges.iter().map(|r| (r.start_line, r.end_line)).collect();
+    assert_eq!(lines, vec![(0, 2), (5, 7)]);
+}
+
+// Semantic Token Tests
+
+/// Decodes semantic tokens into `(line, start, length, type)` tuples.
+fn decode_tokens(text: &str) -> Vec<(u32, u32, u32, SemanticTokenType)> {
+    let file = parse_file(text).unwrap();
+    let (mut line, mut start) = (0, 0);
+    semantic_tokens(text, &file)
+        .into_iter()
+        .map(|token| {
+            if token.delta_line > 0 {
+                start = 0;
+            }
+            line += token.delta_line;
+            start += token.delta_start;
+            (
+                line,
+                start,
+                token.length,
+                TOKEN_TYPES[token.token_type as usize].clone(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_semantic_tokens_classification() {
+    let input = "RouteInfo:\n    routed_gates = CX\n    realize_gate = if x.len() >= 2 then map(|g| -> g, [1.5, 'a']) else []\n";
+    let tokens = decode_tokens(input);
+
+    let expect = |line, start, length, ty: SemanticTokenType| {
+        assert!(
+            tokens.contains(&(line, start, length, ty.clone())),
+            "Missing {:?} at {}:{} in {:?}",
+            ty,
+            line,
+            start,
+            tokens
+        );
+    };
+    expect(0, 0, 9, SemanticTokenType::TYPE);
+    expect(1, 19, 2, SemanticTokenType::TYPE);
+    expect(2, 19, 2, SemanticTokenType::KEYWORD);
+    expect(2, 30, 2, SemanticTokenType::OPERATOR);
+    expect(2, 33, 1, SemanticTokenType::NUMBER);
+    expect(2, 35, 4, SemanticTokenType::KEYWORD);
+    expect(2, 40, 3, SemanticTokenType::FUNCTION);
+    expect(2, 48, 2, SemanticTokenType::OPERATOR);
+    expect(2, 55, 3, SemanticTokenType::NUMBER);
+    expect(2, 60, 3, SemanticTokenType::STRING);
+    expect(2, 66, 4, SemanticTokenType::KEYWORD);
+
+    // Plain variables and field names are left to the client's grammar.
+    assert!(!tokens.iter().any(|t| t.0 == 2 && t.1 == 22));
+}
+
+#[test]
+fn test_semantic_tokens_embedded_rust() {
+    let input = "{{\nfn cost() -> f64 { 1.0 }\n}}\nArch[name = 'a']\n";
+    let tokens = decode_tokens(input);
+
+    let macros: Vec<_> = tokens
+        .iter()
+        .filter(|t| t.3 == SemanticTokenType::MACRO)
+        .map(|t| (t.0, t.1, t.2))
+        .collect();
+    assert_eq!(macros, vec![(0, 0, 2), (1, 0, 24), (2, 0, 2)]);
+    assert!(tokens.contains(&(3, 0, 4, SemanticTokenType::TYPE)));
+}
+
+// Embedded Rust Tests
+
+#[test]
+fn test_rust_regions() {
+    let input = "{{\nfn cost() -> f64 { 1.0 }\n}}\nArch[name = 'a']\n";
+    let file = parse_file(input).unwrap();
+
+    let regions = rust_regions(&file);
+    assert_eq!(
+        regions,
+        vec![Range {
+            start: Position::new(0, 2),
+            end: Position::new(2, 0),
+        }]
+    );
+}
+
+// Signature Help Tests
+
+fn signature_help(input: &str, position: Position) -> Option<SignatureHelp> {
+    let file = parse_file(input).ok();
+    signature_help_at(input, file.as_ref(), position)
+}
+
+#[test]
+fn test_signature_help_active_parameter() {
+    let input = "StateInfo:\n    cost = dist(Location(0), ";
+    let help = signature_help(input, Position::new(1, 30)).expect("Inside dist(");
+
+    let signature = &help.signatures[0];
+    assert_eq!(signature.label, "dist(Location, Location) -> Int");
+    assert_eq!(signature.parameters.as_ref().unwrap().len(), 2);
+    assert_eq!(help.active_parameter, Some(1));
+
+    // The nested Location( call is closed, so its comma-free args don't count.
+    let help = signature_help(input, Position::new(1, 16)).expect("Right after dist(");
+    assert_eq!(help.active_parameter, Some(0));
+}
+
+#[test]
+fn test_signature_help_in_parsed_call() {
+    let input = "StateInfo:\n    cost = dist(Location(0), [Location(1)][0])";
+    let help = signature_help(input, Position::new(1, 31)).expect("Inside the list argument");
+    assert_eq!(help.signatures[0].label, "dist(Location, Location) -> Int");
+    assert_eq!(help.active_parameter, Some(1));
+
+    // On the callee itself there is no call yet
+    assert!(signature_help(input, Position::new(1, 13)).is_none());
+}
+
+#[test]
+fn test_signature_help_outside_call() {
+    let input = "StateInfo:\n    cost = [dist, 1]\n    other = unknown_fn(1, 2)";
+    assert!(signature_help(input, Position::new(1, 16)).is_none());
+    assert!(signature_help(input, Position::new(2, 23)).is_none());
+}
+
+// Inlay Hint Tests
+
+fn hint_labels(text: &str, range: Range) -> Vec<(Position, String)> {
+    let file = parse_file(text).unwrap();
+    inlay_hints(&file, range)
+        .into_iter()
+        .map(|hint| match hint.label {
+            InlayHintLabel::String(label) => (hint.position, label),
+            other => panic!("Expected string label, got: {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_inlay_hints_show_inferred_types() {
+    let input =
+        "let base = 2\nStateInfo:\n    cost = 0.0\n    name = mystery\n    count = base + 1\n";
+    let everything = Range::new(Position::new(0, 0), Position::new(10, 0));
+
+    let hints = hint_labels(input, everything);
+    assert_eq!(
+        hints,
+        vec![
+            (Position::new(2, 14), ": Float".to_string()),
+            (Position::new(4, 20), ": Int".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_inlay_hints_respect_range() {
+    let input = "StateInfo:\n    cost = 0.0\n    flag = true\n";
+    let hints = hint_labels(input, Range::new(Position::new(2, 0), Position::new(2, 20)));
+    assert_eq!(hints, vec![(Position::new(2, 15), ": Bool".to_string())]);
+}
+
+// Rename Tests
+
+#[test]
+fn test_rename_block_updates_references() {
+    let input = "Step[\n    name = 's'\n]\nRouteInfo:\n    realize_gate = Step\n    routed_gates = [Step, CX]\n";
+    let file = parse_file(input).unwrap();
+    let uri = Url::parse("file:///test.qmrl").unwrap();
+
+    assert_eq!(
+        prepare_rename_at(&file, Position::new(4, 21)),
+        Some(Range::new(Position::new(4, 19), Position::new(4, 23)))
+    );
+
+    let edit = rename_at(&uri, &file, Position::new(0, 1), "Phase").unwrap();
+    let mut edits = edit.changes.unwrap().remove(&uri).unwrap();
+    edits.sort_by_key(|e| e.range.start);
+
+    let ranges: Vec<_> = edits.iter().map(|e| e.range).collect();
+    assert_eq!(
+        ranges,
+        vec![
+            Range::new(Position::new(0, 0), Position::new(0, 4)),
+            Range::new(Position::new(4, 19), Position::new(4, 23)),
+            Range::new(Position::new(5, 20), Position::new(5, 24)),
+        ]
+    );
+    assert!(edits.iter().all(|e| e.new_text == "Phase"));
+}
+
+#[test]
+fn test_rename_block_updates_top_level_bindings() {
+    let input = "let first = Step\nStep[\n    name = 's'\n]\nRouteInfo:\n    realize_gate = Step\n";
+    let file = parse_file(input).unwrap();
+    let uri = Url::parse("file:///test.qmrl").unwrap();
+
+    let refs: Vec<_> = references_at(&uri, &file, Position::new(1, 1), false)
+        .into_iter()
+        .map(|location| location.range.start)
+        .collect();
+    assert_eq!(refs, vec![Position::new(5, 19), Position::new(0, 12)]);
+
+    let edit = rename_at(&uri, &file, Position::new(1, 1), "Phase").unwrap();
+    let mut ranges: Vec<_> = edit.changes.unwrap()[&uri]
+        .iter()
+        .map(|e| e.range)
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    assert_eq!(
+        ranges,
+        vec![
+            Range::new(Position::new(0, 12), Position::new(0, 16)),
+            Range::new(Position::new(1, 0), Position::new(1, 4)),
+            Range::new(Position::new(5, 19), Position::new(5, 23)),
+        ]
+    );
+}
+
+#[test]
+fn test_rename_rejections() {
+    let input = "Step[\n    name = 's'\n]\nArch[\n    name = 'a'\n]\n";
+    let file = parse_file(input).unwrap();
+    let uri = Url::parse("file:///test.qmrl").unwrap();
+    let on_step = Position::new(0, 1);
+
+    assert!(prepare_rename_at(&file, Position::new(1, 5)).is_none());
+    assert!(rename_at(&uri, &file, Position::new(1, 5), "x").is_err());
+    assert!(rename_at(&uri, &file, on_step, "Arch").is_err());
+    assert!(rename_at(&uri, &file, on_step, "let").is_err());
+    assert!(rename_at(&uri, &file, on_step, "9lives").is_err());
+}
+
+// AST Dump Tests
+
+#[test]
+fn test_dump_ast_pretty_prints_file() {
+    let dump = dump_ast(&parse_file("RouteInfo:\n    routed_gates = CX\n"));
+    assert!(dump.starts_with("AmaroFile {"), "Got: {}", dump);
+    assert!(dump.contains("kind: \"RouteInfo\""), "Got: {}", dump);
+    assert!(dump.contains("key: \"routed_gates\""), "Got: {}", dump);
+}
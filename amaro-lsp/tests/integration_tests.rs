@@ -1,4 +1,8 @@
+use std::path::Path;
+
 use amaro_lsp::parser::core::parse_file;
+use amaro_lsp::parser::diagnose;
+use tower_lsp::lsp_types::DiagnosticSeverity;
 
 const FILE_1: &str = include_str!("../test_files/file1.qmrl");
 const FILE_3: &str = include_str!("../test_files/file3.qmrl");
@@ -25,3 +29,76 @@ fn test_file_5_parsing() {
     let result = parse_file(FILE_5);
     assert!(result.is_ok());
 }
+
+/// Renders diagnostics one per line as `line:col: severity: message`.
+fn render_diagnostics(source: &str) -> String {
+    diagnose(source)
+        .iter()
+        .map(|d| {
+            let severity = match d.severity {
+                Some(DiagnosticSeverity::WARNING) => "warning",
+                Some(DiagnosticSeverity::INFORMATION) => "info",
+                Some(DiagnosticSeverity::HINT) => "hint",
+                _ => "error",
+            };
+            format!(
+                "{}:{}: {}: {}\n",
+                d.range.start.line + 1,
+                d.range.start.character + 1,
+                severity,
+                d.message
+            )
+        })
+        .collect()
+}
+
+/// Runs the full diagnostics pipeline over every `.qmrl` fixture in
+/// `test_files/` and `../examples/`, comparing against the sidecar
+/// `<name>.expected` next to it. A fixture without a sidecar must be clean.
+///
+/// Run with `UPDATE_EXPECTED=1` to rewrite the sidecars from the current
+/// output.
+#[test]
+fn test_canonical_files_match_expected_diagnostics() {
+    let update = std::env::var_os("UPDATE_EXPECTED").is_some();
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut checked = 0;
+    let mut failures = Vec::new();
+
+    for dir in [root.join("test_files"), root.join("../examples")] {
+        let mut paths: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "qmrl"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let actual = render_diagnostics(&std::fs::read_to_string(&path).unwrap());
+            let sidecar = path.with_extension("expected");
+            checked += 1;
+
+            if update {
+                if actual.is_empty() {
+                    let _ = std::fs::remove_file(&sidecar);
+                } else {
+                    std::fs::write(&sidecar, &actual).unwrap();
+                }
+                continue;
+            }
+
+            let expected = std::fs::read_to_string(&sidecar).unwrap_or_default();
+            if actual != expected {
+                failures.push(format!(
+                    "{}\n--- expected\n{}--- actual\n{}",
+                    path.display(),
+                    expected,
+                    actual
+                ));
+            }
+        }
+    }
+
+    assert!(checked > 0, "No .qmrl fixtures found");
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -1,7 +1,12 @@
 use amaro_lsp::ast::*;
+use amaro_lsp::parser::navigation::{block_at_position, node_at_position};
+use amaro_lsp::parser::symbols::Type;
+use amaro_lsp::parser::types::parse_type;
 use amaro_lsp::parser::{
-    consume_remaining_block, parse_file, parse_identifier, parse_rust_embedded_robust,
+    AnalysisConfig, consume_remaining_block, parse_file, parse_identifier,
+    parse_rust_embedded_robust,
 };
+use tower_lsp::lsp_types::{Position, Range};
 
 // Helper to extract first field's value expression from parsed file
 fn get_first_field_value(file: AmaroFile) -> Expr {
@@ -40,6 +45,27 @@ fn test_parse_rust_embedded_inline() {
     assert!(parse_rust_embedded_robust(input).is_ok());
 }
 
+#[test]
+fn test_parse_rust_embedded_nested_closing_braces() {
+    let input = "{{ fn f() -> T { Foo{} } }}\nRouteInfo:";
+    let (rest, content) = parse_rust_embedded_robust(input).unwrap();
+    assert_eq!(content, " fn f() -> T { Foo{} } ");
+    assert_eq!(rest, "\nRouteInfo:");
+
+    let input = "{{ fn g() -> Bar { Bar { x: Foo {}}} }}rest";
+    let (rest, content) = parse_rust_embedded_robust(input).unwrap();
+    assert_eq!(content, " fn g() -> Bar { Bar { x: Foo {}}} ");
+    assert_eq!(rest, "rest");
+}
+
+#[test]
+fn test_parse_rust_embedded_lifetimes_and_char_literals() {
+    let input = "{{ fn f<'a>(x: &'a str) -> char { if x.is_empty() { '}' } else { '\\'' } } }}rest";
+    let (rest, content) = parse_rust_embedded_robust(input).unwrap();
+    assert!(content.ends_with("else { '\\'' } } "), "Got: {}", content);
+    assert_eq!(rest, "rest");
+}
+
 #[test]
 fn test_parse_identifier_invalid() {
     assert!(parse_identifier("123Invalid").is_err());
@@ -144,10 +170,48 @@ GateRealization[
 name = 'next'
 ]"#;
 
-    let (rest, _) = consume_remaining_block(input).unwrap();
+    let (rest, _) = consume_remaining_block(input, &AnalysisConfig::default()).unwrap();
     assert!(rest.starts_with("GateRealization["));
 }
 
+#[test]
+fn test_consume_ignores_indented_non_block_lines() {
+    let input = "cost = 1.0\n    edges[0]\n    Step[\nFooInfo:\n    x = 1";
+
+    let (rest, body) = consume_remaining_block(input, &AnalysisConfig::default()).unwrap();
+    assert!(body.contains("edges[0]"));
+    assert!(rest.starts_with("    Step["), "Got rest: {:?}", rest);
+
+    let (rest, _) =
+        consume_remaining_block(&rest["    Step[".len()..], &AnalysisConfig::default()).unwrap();
+    assert!(rest.starts_with("FooInfo:"), "Got rest: {:?}", rest);
+}
+
+#[test]
+fn test_consume_ignores_block_names_inside_bodies() {
+    let config = AnalysisConfig::default();
+
+    // A struct parameter named like a block, and a value mentioning Arch
+    let input = "    Transition{\n        step : Int,\n        Arch : Int}\n    cost = f(1,\n        Arch)\nStateInfo:\n";
+    let (rest, body) = consume_remaining_block(input, &config).unwrap();
+    assert!(body.contains("        Arch)"), "Got body: {:?}", body);
+    assert!(rest.starts_with("StateInfo:"), "Got rest: {:?}", rest);
+
+    // An indented header on its own line still starts a block
+    let (rest, _) = consume_remaining_block("    x = 1\n    Arch:  // shape\n", &config).unwrap();
+    assert!(rest.starts_with("    Arch:"), "Got rest: {:?}", rest);
+}
+
+#[test]
+fn test_field_referencing_arch_stays_in_block() {
+    let input = "StateInfo:\n    cost = foo(1,\n        Arch\n        )\n    other = 2\n";
+    let file = parse_file(input).unwrap();
+
+    assert_eq!(file.blocks.len(), 1);
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    assert_eq!(items.len(), 2, "Got: {:?}", items);
+}
+
 #[test]
 fn test_consecutive_colon_blocks() {
     let input = r#"RouteInfo:
@@ -166,6 +230,41 @@ StateInfo:
     assert_eq!(file.blocks.len(), 4);
 }
 
+#[test]
+fn test_multiline_fields_stop_at_next_colon_block() {
+    let input = r#"RouteInfo:
+    routed_gates = CX
+    realize_gate =
+        map(|x| -> x,
+            Vec(Location(0),
+                Location(1)))
+TransitionInfo:
+    get_transitions =
+        if true
+        then Vec()
+        else Vec()
+    apply = identity_application(step)
+    cost =
+        1.0
+"#;
+
+    let file = parse_file(input).unwrap();
+    assert!(file.syntax_errors.is_empty(), "{:?}", file.syntax_errors);
+    let summary: Vec<_> = file
+        .blocks
+        .iter()
+        .map(|block| {
+            let BlockContent::Fields(items) = &block.content;
+            (block.kind.as_str(), items.len())
+        })
+        .collect();
+    assert_eq!(summary, vec![("RouteInfo", 2), ("TransitionInfo", 3)]);
+
+    let realize = file.blocks[0].field("realize_gate").unwrap();
+    assert!(matches!(realize.value.kind, ExprKind::FunctionCall { .. }));
+    assert_eq!(realize.value.range.start.line, 3);
+}
+
 #[test]
 fn test_consume_stops_at_next_colon_block() {
     let input = r#"routed_gates = CX
@@ -173,7 +272,7 @@ data = test
 TransitionInfo:
 more data"#;
 
-    let (rest, _) = consume_remaining_block(input).unwrap();
+    let (rest, _) = consume_remaining_block(input, &AnalysisConfig::default()).unwrap();
     assert!(rest.starts_with("TransitionInfo:"));
 }
 
@@ -186,7 +285,7 @@ realize_gate = Some(value)
 RouteInfo:
 next block"#;
 
-    let (rest, _) = consume_remaining_block(input).unwrap();
+    let (rest, _) = consume_remaining_block(input, &AnalysisConfig::default()).unwrap();
     assert!(rest.starts_with("RouteInfo:"));
 }
 
@@ -225,6 +324,56 @@ fn test_if_then_else_expression() {
     }
 }
 
+/// Splits `if c then t else e` into its parts, with each part's start column.
+fn if_parts(expr: &Expr) -> (&Expr, &Expr, &Expr) {
+    let ExprKind::IfThenElse {
+        condition,
+        then_branch,
+        else_branch,
+    } = &expr.kind
+    else {
+        panic!("Expected if-then-else, got {:?}", expr.kind);
+    };
+    (condition, then_branch, else_branch)
+}
+
+#[test]
+fn test_three_way_if_chain() {
+    for (source, inner_start, last_start) in [
+        ("if a then 1 else if b then 2 else 3", 28, 45),
+        ("if a then 1 elif b then 2 else 3", 23, 42),
+    ] {
+        let input = format!("StateInfo:\n    cost = {}", source);
+        let value = get_first_field_value(parse_file(&input).unwrap());
+        assert_eq!(value.range.start, Position::new(1, 11), "{}", source);
+        assert_eq!(value.range.end, Position::new(1, 11 + source.len() as u32));
+
+        let (condition, first, rest) = if_parts(&value);
+        assert!(matches!(&condition.kind, ExprKind::Identifier(n) if n == "a"));
+        assert!(matches!(first.kind, ExprKind::IntLiteral(1)));
+        assert_eq!(first.range.start, Position::new(1, 21));
+
+        let (condition, second, last) = if_parts(rest);
+        assert_eq!(
+            rest.range.start,
+            Position::new(1, inner_start),
+            "{}",
+            source
+        );
+        assert_eq!(rest.range.end, value.range.end);
+        assert!(matches!(&condition.kind, ExprKind::Identifier(n) if n == "b"));
+        assert!(matches!(second.kind, ExprKind::IntLiteral(2)));
+        assert!(matches!(last.kind, ExprKind::IntLiteral(3)));
+        assert_eq!(last.range.start, Position::new(1, last_start));
+    }
+}
+
+#[test]
+fn test_if_keyword_prefix_is_an_identifier() {
+    let value = get_first_field_value(parse_file("StateInfo:\n    cost = iffy").unwrap());
+    assert!(matches!(&value.kind, ExprKind::Identifier(n) if n == "iffy"));
+}
+
 #[test]
 fn test_let_binding_expression() {
     let input = r#"TransitionInfo:
@@ -290,13 +439,46 @@ fn test_comparison_operators() {
     let file = parse_file(input).unwrap();
 
     let BlockContent::Fields(items) = &file.blocks[0].content;
-    if let Some(BlockItem::Field(field)) = items.first() {
-        if let ExprKind::IfThenElse { condition, .. } = &field.value.kind {
-            assert!(matches!(condition.kind, ExprKind::BinaryOp { .. }));
+    if let Some(BlockItem::Field(field)) = items.first()
+        && let ExprKind::IfThenElse { condition, .. } = &field.value.kind
+    {
+        assert!(matches!(condition.kind, ExprKind::BinaryOp { .. }));
+    }
+}
+
+#[test]
+fn test_tensor_operator_symbol() {
+    let input = "RouteInfo:\n    realize_gate = a ⊗ b";
+    let value = get_first_field_value(parse_file(input).unwrap());
+
+    match value.kind {
+        ExprKind::BinaryOp {
+            op: BinaryOperator::Tensor,
+            left,
+            right,
+        } => {
+            assert!(matches!(left.kind, ExprKind::Identifier(ref n) if n == "a"));
+            assert!(matches!(right.kind, ExprKind::Identifier(ref n) if n == "b"));
+            // '⊗' counts as a single UTF-16 column
+            assert_eq!(right.range.start.character, 23);
         }
+        other => panic!("Expected tensor BinaryOp, got: {:?}", other),
     }
 }
 
+#[test]
+fn test_tensor_operator_keyword() {
+    let input = "RouteInfo:\n    realize_gate = a tensor b";
+    let value = get_first_field_value(parse_file(input).unwrap());
+    assert!(matches!(
+        value.kind,
+        ExprKind::BinaryOp {
+            op: BinaryOperator::Tensor,
+            ..
+        }
+    ));
+}
+
 // 4. Edge Case & Recovery Tests
 
 #[test]
@@ -327,6 +509,29 @@ Architecture[
     assert_eq!(file.blocks.len(), 3);
 }
 
+#[test]
+fn test_top_level_let_bindings() {
+    let input =
+        "let end_cost = 1.5\nlet junction_count = 4;\n\nArchitecture[\n    name = 'IonArch'\n]";
+
+    let file = parse_file(input).unwrap();
+    assert_eq!(file.blocks.len(), 1);
+    assert_eq!(file.bindings.len(), 2);
+
+    let end_cost = &file.bindings[0];
+    assert_eq!(end_cost.name, "end_cost");
+    assert_eq!(end_cost.name_range.start.character, 4);
+    assert_eq!(end_cost.name_range.end.character, 12);
+    assert!(matches!(end_cost.value.kind, ExprKind::FloatLiteral(f) if f == 1.5));
+    assert_eq!(end_cost.range.start.line, 0);
+    assert_eq!(end_cost.range.start.character, 0);
+
+    let junction_count = &file.bindings[1];
+    assert_eq!(junction_count.name, "junction_count");
+    assert_eq!(junction_count.range.start.line, 1);
+    assert!(matches!(junction_count.value.kind, ExprKind::IntLiteral(4)));
+}
+
 #[test]
 fn test_empty_file() {
     let file = parse_file("").unwrap();
@@ -366,6 +571,64 @@ Transition[
     assert_eq!(file.blocks[1].kind, "Transition");
 }
 
+#[test]
+fn test_block_fields_are_retained() {
+    let input = r#"RouteInfo:
+    routed_gates = CX
+    realize_gate = Some(value)"#;
+
+    let file = parse_file(input).unwrap();
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    let keys: Vec<_> = items
+        .iter()
+        .map(|item| match item {
+            BlockItem::Field(f) => f.key.as_str(),
+            BlockItem::StructDef(s) => s.name.as_str(),
+        })
+        .collect();
+    assert_eq!(keys, vec!["routed_gates", "realize_gate"]);
+
+    let BlockItem::Field(field) = &items[1] else {
+        panic!("Expected field");
+    };
+    assert!(matches!(field.value.kind, ExprKind::Some(_)));
+    assert_eq!(field.key_range.start.line, 2);
+    assert_eq!(field.key_range.start.character, 4);
+}
+
+#[test]
+fn test_malformed_field_does_not_abort_block() {
+    let input = r#"RouteInfo:
+    routed_gates = CX
+    broken = (1,
+    realize_gate = x
+
+GateRealization[
+    name = 'a'
+    bad = = 3
+    other = 2
+]"#;
+
+    let file = parse_file(input).unwrap();
+    assert_eq!(file.blocks.len(), 2);
+
+    for (block, expected) in file
+        .blocks
+        .iter()
+        .zip([["routed_gates", "realize_gate"], ["name", "other"]])
+    {
+        let BlockContent::Fields(items) = &block.content;
+        let keys: Vec<_> = items
+            .iter()
+            .filter_map(|item| match item {
+                BlockItem::Field(f) => Some(f.key.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(keys, expected, "Fields of {}", block.kind);
+    }
+}
+
 #[test]
 fn test_all_rust_block_positions() {
     // Rust block at start
@@ -395,6 +658,25 @@ fn test_windows_line_endings() {
     assert_eq!(file.blocks.len(), 2);
 }
 
+#[test]
+fn test_windows_line_endings_columns() {
+    let input = "RouteInfo:\r\n  routed_gates = CX\r\nTransitionInfo:\r\n  data = test";
+    let file = parse_file(input).unwrap();
+
+    let block = &file.blocks[1];
+    assert_eq!(block.range.start.line, 2);
+    assert_eq!(block.range.start.character, 0);
+    assert_eq!(block.range.end.character, 14);
+
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    let BlockItem::Field(field) = &items[0] else {
+        panic!("Expected field");
+    };
+    assert_eq!(field.key_range.start.line, 1);
+    assert_eq!(field.key_range.start.character, 2);
+    assert_eq!(field.value_range.end.character, 19);
+}
+
 #[test]
 fn test_file_no_trailing_newline() {
     let input = r#"GateRealization[
@@ -597,6 +879,60 @@ fn test_multiline_with_comments() {
     }
 }
 
+#[test]
+fn test_multiline_let_chain_field_value_is_one_expression() {
+    let input = r#"RouteInfo:
+    realize_gate = 
+        if (Gate.gate_type()) == CX 
+        then 
+            (let v = Vec() in
+            let v2 = v.push(Location(0)) in
+            let v3 = v.extend(v2) in
+
+            all_paths(Arch, 
+                        vertical_neighbors(State.map[Gate.qubits[0]], 10, 10), 
+                        Vec()))
+        else 
+            Vec()
+    routed_gates = CX
+
+TransitionInfo:
+    cost = 0.0"#;
+
+    let file = parse_file(input).unwrap();
+    assert!(
+        file.syntax_errors.is_empty(),
+        "Got: {:?}",
+        file.syntax_errors
+    );
+    assert_eq!(file.blocks.len(), 2);
+
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    assert_eq!(items.len(), 2);
+    let BlockItem::Field(field) = &items[0] else {
+        panic!("Expected realize_gate field, got: {:?}", items[0]);
+    };
+    assert_eq!(field.key, "realize_gate");
+    assert_eq!(field.value_range.start.line, 2);
+
+    let ExprKind::IfThenElse {
+        then_branch,
+        else_branch,
+        ..
+    } = &field.value.kind
+    else {
+        panic!("Expected if-then-else, got: {:?}", field.value.kind);
+    };
+    assert_eq!(else_branch.range.start.line, 12);
+    let ExprKind::LetBinding { name, body, .. } = &then_branch.kind else {
+        panic!("Expected let chain, got: {:?}", then_branch.kind);
+    };
+    assert_eq!(name, "v");
+    assert!(matches!(body.kind, ExprKind::LetBinding { .. }));
+
+    assert!(matches!(&items[1], BlockItem::Field(f) if f.key == "routed_gates"));
+}
+
 #[test]
 fn test_comma_separated_gates_no_brackets() {
     let input = r#"RouteInfo:
@@ -839,29 +1175,29 @@ fn test_nested_comparisons() {
     let file = parse_file(input).unwrap();
 
     let BlockContent::Fields(items) = &file.blocks[0].content;
-    if let Some(BlockItem::Field(field)) = items.first() {
-        if let ExprKind::IfThenElse { condition, .. } = &field.value.kind {
-            if let ExprKind::BinaryOp { op, left, right } = &condition.kind {
-                assert_eq!(*op, BinaryOperator::And);
-                // Left should be x == y
-                assert!(matches!(
-                    left.kind,
-                    ExprKind::BinaryOp {
-                        op: BinaryOperator::Eq,
-                        ..
-                    }
-                ));
-                // Right should be a < b
-                assert!(matches!(
-                    right.kind,
-                    ExprKind::BinaryOp {
-                        op: BinaryOperator::Lt,
-                        ..
-                    }
-                ));
-            } else {
-                panic!("Expected BinaryOp with And");
-            }
+    if let Some(BlockItem::Field(field)) = items.first()
+        && let ExprKind::IfThenElse { condition, .. } = &field.value.kind
+    {
+        if let ExprKind::BinaryOp { op, left, right } = &condition.kind {
+            assert_eq!(*op, BinaryOperator::And);
+            // Left should be x == y
+            assert!(matches!(
+                left.kind,
+                ExprKind::BinaryOp {
+                    op: BinaryOperator::Eq,
+                    ..
+                }
+            ));
+            // Right should be a < b
+            assert!(matches!(
+                right.kind,
+                ExprKind::BinaryOp {
+                    op: BinaryOperator::Lt,
+                    ..
+                }
+            ));
+        } else {
+            panic!("Expected BinaryOp with And");
         }
     }
 }
@@ -874,16 +1210,16 @@ fn test_parenthesized_comparison() {
     let file = parse_file(input).unwrap();
 
     let BlockContent::Fields(items) = &file.blocks[0].content;
-    if let Some(BlockItem::Field(field)) = items.first() {
-        if let ExprKind::IfThenElse { condition, .. } = &field.value.kind {
-            assert!(matches!(
-                condition.kind,
-                ExprKind::BinaryOp {
-                    op: BinaryOperator::Eq,
-                    ..
-                }
-            ));
-        }
+    if let Some(BlockItem::Field(field)) = items.first()
+        && let ExprKind::IfThenElse { condition, .. } = &field.value.kind
+    {
+        assert!(matches!(
+            condition.kind,
+            ExprKind::BinaryOp {
+                op: BinaryOperator::Eq,
+                ..
+            }
+        ));
     }
 }
 
@@ -927,10 +1263,10 @@ fn test_newline_before_arrow_in_lambda() {
     let file = parse_file(input).unwrap();
 
     let BlockContent::Fields(items) = &file.blocks[0].content;
-    if let Some(BlockItem::Field(field)) = items.first() {
-        if let ExprKind::FunctionCall { args, .. } = &field.value.kind {
-            assert!(matches!(args[0].kind, ExprKind::Lambda { .. }));
-        }
+    if let Some(BlockItem::Field(field)) = items.first()
+        && let ExprKind::FunctionCall { args, .. } = &field.value.kind
+    {
+        assert!(matches!(args[0].kind, ExprKind::Lambda { .. }));
     }
 }
 
@@ -1048,11 +1384,703 @@ fn test_complex_chaining() {
     let file = parse_file(input).unwrap();
 
     let BlockContent::Fields(items) = &file.blocks[0].content;
-    if let Some(BlockItem::Field(field)) = items.first() {
-        if let ExprKind::FunctionCall { args, .. } = &field.value.kind {
-            if let ExprKind::Lambda { body, .. } = &args[0].kind {
-                assert!(matches!(body.kind, ExprKind::IndexAccess { .. }));
-            }
-        }
+    if let Some(BlockItem::Field(field)) = items.first()
+        && let ExprKind::FunctionCall { args, .. } = &field.value.kind
+        && let ExprKind::Lambda { body, .. } = &args[0].kind
+    {
+        assert!(matches!(body.kind, ExprKind::IndexAccess { .. }));
+    }
+}
+
+#[test]
+fn test_postfix_and_unary_ranges_on_later_lines() {
+    let input = "RouteInfo:\n    routed_gates = CX\n    realize_gate = foo(a).b\n    cost = -x";
+    let file = parse_file(input).unwrap();
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+
+    let BlockItem::Field(call) = &items[1] else {
+        panic!("Expected field");
+    };
+    assert_eq!(call.value.range.start.line, 2);
+    assert_eq!(call.value.range.start.character, 19);
+    assert_eq!(call.value.range.end.character, 27);
+
+    let BlockItem::Field(neg) = &items[2] else {
+        panic!("Expected field");
+    };
+    assert!(matches!(neg.value.kind, ExprKind::UnaryOp { .. }));
+    assert_eq!(neg.value.range.start.line, 3);
+    assert_eq!(neg.value.range.start.character, 11);
+    assert_eq!(neg.value.range.end.character, 13);
+}
+
+#[test]
+fn test_unary_range_on_later_line() {
+    let input = "StateInfo:\n    a = 1\n    b = 2\n    c = 3\n    cost = -count";
+    let file = parse_file(input).unwrap();
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    let BlockItem::Field(field) = &items[3] else {
+        panic!("Expected field");
+    };
+
+    let ExprKind::UnaryOp { operand, .. } = &field.value.kind else {
+        panic!("Expected UnaryOp, got: {:?}", field.value.kind);
+    };
+    assert_eq!(field.value.range.start.line, 4);
+    assert_eq!(field.value.range.start.character, 11);
+    assert_eq!(field.value.range.end.line, 4);
+    assert_eq!(field.value.range.end.character, 17);
+    assert_eq!(operand.range.start.character, 12);
+}
+
+#[test]
+fn test_postfix_range_on_later_line() {
+    let input = "RouteInfo:\n    routed_gates = CX\n    realize_gate = State.map[Gate.qubits[0]]";
+    let file = parse_file(input).unwrap();
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    let BlockItem::Field(field) = &items[1] else {
+        panic!("Expected field");
+    };
+    let value = &field.value;
+
+    let ExprKind::IndexAccess { object, index } = &value.kind else {
+        panic!("Expected IndexAccess, got: {:?}", value.kind);
+    };
+    assert_eq!(value.range.start.line, 2);
+    assert_eq!(value.range.start.character, 19);
+    assert_eq!(value.range.end.line, 2);
+    assert_eq!(value.range.end.character, 44);
+
+    // Nested postfix chains are anchored on their own primary expression
+    assert_eq!(object.range.start.character, 19);
+    assert_eq!(index.range.start.line, 2);
+    assert_eq!(index.range.start.character, 29);
+}
+
+/// Parses `source` as the value of a field, which starts at column 8.
+fn parse_field_value(source: &str) -> Expr {
+    let input = format!("StateInfo:\n    x = {}", source);
+    get_first_field_value(parse_file(&input).unwrap())
+}
+
+#[test]
+fn test_string_literal_quote_styles() {
+    for source in ["'CNOT'", "\"CNOT\""] {
+        let value = parse_field_value(source);
+        assert!(
+            matches!(value.kind, ExprKind::StringLiteral(ref s) if s == "CNOT"),
+            "{} parsed as {:?}",
+            source,
+            value.kind
+        );
+        assert_eq!(value.range.start.character, 8);
+        assert_eq!(value.range.end.character, 14);
+    }
+}
+
+#[test]
+fn test_string_literal_escapes() {
+    let value = parse_field_value(r"'it\'s'");
+    assert!(matches!(value.kind, ExprKind::StringLiteral(ref s) if s == "it's"));
+    // Range covers the source text, not the decoded content
+    assert_eq!(value.range.end.character, 15);
+
+    let value = parse_field_value(r#""a\tb\n\\ \"q\" it's""#);
+    assert!(
+        matches!(value.kind, ExprKind::StringLiteral(ref s) if s == "a\tb\n\\ \"q\" it's"),
+        "Got: {:?}",
+        value.kind
+    );
+}
+
+#[test]
+fn test_unterminated_string_does_not_consume_file() {
+    let input = "GateRealization[\n    name = 'IonCNOT\n    other = 'ok'\n]";
+    let file = parse_file(input).unwrap();
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    let keys: Vec<_> = items
+        .iter()
+        .filter_map(|item| match item {
+            BlockItem::Field(f) => Some(f.key.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(keys, vec!["other"]);
+}
+
+#[test]
+fn test_unterminated_string_reports_syntax_error() {
+    let input = "GateRealization[\n    name = 'IonCNOT\n    other = 'ok' // it's fine\n]";
+    let file = parse_file(input).unwrap();
+
+    assert_eq!(file.syntax_errors.len(), 1, "Got: {:?}", file.syntax_errors);
+    let error = &file.syntax_errors[0];
+    assert_eq!(error.message, "Unterminated string literal");
+    // From the opening quote to the end of the line
+    assert_eq!(error.range.start.line, 1);
+    assert_eq!(error.range.start.character, 11);
+    assert_eq!(error.range.end.line, 1);
+    assert_eq!(error.range.end.character, 19);
+}
+
+#[test]
+fn test_unterminated_string_in_colon_block_crlf() {
+    let input = "RouteInfo:\r\n    routed_gates = \"CX\r\n    realize_gate = []\r\n";
+    let file = parse_file(input).unwrap();
+
+    assert_eq!(file.syntax_errors.len(), 1);
+    let range = file.syntax_errors[0].range;
+    assert_eq!(range.start.character, 19);
+    assert_eq!(range.end.character, 22);
+}
+
+#[test]
+fn test_valid_file_has_no_syntax_errors() {
+    let input = "GateRealization[\n    name = 'it\\'s'\n    other = \"//\"\n]";
+    let file = parse_file(input).unwrap();
+    assert!(
+        file.syntax_errors.is_empty(),
+        "Got: {:?}",
+        file.syntax_errors
+    );
+}
+
+#[test]
+fn test_comment_markers_inside_strings_are_kept() {
+    let input = "Architecture[\n    name = 'http://x' // real comment ]\n    note = \"a]b[\"\n]\nStateInfo:\n    url = 'http://y'\n    cost = 1.0\n";
+    let file = parse_file(input).unwrap();
+    assert!(
+        file.syntax_errors.is_empty(),
+        "Got: {:?}",
+        file.syntax_errors
+    );
+    assert_eq!(file.blocks.len(), 2);
+
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    let values: Vec<_> = items
+        .iter()
+        .filter_map(|item| match item {
+            BlockItem::Field(f) => match &f.value.kind {
+                ExprKind::StringLiteral(s) => Some(s.as_str()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    assert_eq!(values, vec!["http://x", "a]b["]);
+    assert_eq!(file.blocks[0].outer_range.end, Position::new(3, 1));
+
+    let BlockContent::Fields(items) = &file.blocks[1].content;
+    assert!(matches!(&items[0], BlockItem::Field(f)
+        if matches!(&f.value.kind, ExprKind::StringLiteral(s) if s == "http://y")));
+}
+
+#[test]
+fn test_unclosed_bracket_block_reports_error() {
+    let input =
+        "GateRealization[\n    name = 'IonCNOT'\n\nArchitecture[\n    name = 'IonArch'\n]\n";
+    let file = parse_file(input).unwrap();
+
+    assert_eq!(file.syntax_errors.len(), 1, "Got: {:?}", file.syntax_errors);
+    let error = &file.syntax_errors[0];
+    assert_eq!(error.message, "Unclosed '[' for block 'GateRealization'");
+    assert_eq!(error.range.start, Position::new(0, 15));
+    assert_eq!(error.range.end, Position::new(0, 16));
+
+    // The unclosed block keeps its fields and ends before the next block
+    let kinds: Vec<_> = file.blocks.iter().map(|b| b.kind.as_str()).collect();
+    assert_eq!(kinds, vec!["GateRealization", "Architecture"]);
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    assert_eq!(items.len(), 1);
+    assert_eq!(file.blocks[0].outer_range.end, Position::new(1, 20));
+}
+
+#[test]
+fn test_malformed_field_reports_syntax_error_at_line() {
+    let input = "RouteInfo:\n    routed_gates = CX\n    realize_gate = map(|x| x, Vec())\n    // trailing comment\n";
+    let file = parse_file(input).unwrap();
+
+    assert_eq!(file.syntax_errors.len(), 1, "Got: {:?}", file.syntax_errors);
+    let error = &file.syntax_errors[0];
+    assert_eq!(error.message, "Invalid value for field 'realize_gate'.");
+    assert_eq!(error.range.start, Position::new(2, 4));
+    assert_eq!(error.range.end, Position::new(2, 36));
+}
+
+#[test]
+fn test_malformed_multiline_field_reports_once() {
+    let input =
+        "StateInfo:\n    cost = map(|x| x,\n        Vec(),\n\n        Vec())\n    other = 1\n";
+    let file = parse_file(input).unwrap();
+
+    assert_eq!(file.syntax_errors.len(), 1, "Got: {:?}", file.syntax_errors);
+    assert_eq!(file.syntax_errors[0].range.start, Position::new(1, 4));
+
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    assert_eq!(items.len(), 1);
+    assert!(matches!(&items[0], BlockItem::Field(f) if f.key == "other"));
+}
+
+#[test]
+fn test_stray_line_reports_syntax_error() {
+    let input = "StateInfo:\n    cost = 1.0\n    ???\n";
+    let file = parse_file(input).unwrap();
+
+    assert_eq!(file.syntax_errors.len(), 1, "Got: {:?}", file.syntax_errors);
+    assert_eq!(
+        file.syntax_errors[0].message,
+        "Expected a field (`name = value`) or struct definition."
+    );
+    assert_eq!(file.syntax_errors[0].range.start, Position::new(2, 4));
+}
+
+#[test]
+fn test_let_without_in_reports_missing_in() {
+    let input = "StateInfo:\n    cost = let x = 5 // no body\n    other = 1\n";
+    let file = parse_file(input).unwrap();
+
+    assert_eq!(file.syntax_errors.len(), 1, "Got: {:?}", file.syntax_errors);
+    let error = &file.syntax_errors[0];
+    assert_eq!(error.message, "Expected 'in' after let binding");
+    assert_eq!(error.range.start, Position::new(1, 20));
+    assert_eq!(error.range.end, Position::new(1, 20));
+
+    // The field keeps the bound value and parsing continues
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    assert_eq!(items.len(), 2);
+    assert!(
+        matches!(&items[0], BlockItem::Field(f) if matches!(f.value.kind, ExprKind::IntLiteral(5)))
+    );
+}
+
+#[test]
+fn test_let_body_starting_with_in_is_not_in() {
+    let input = "StateInfo:\n    cost = let x = 1 index\n";
+    let file = parse_file(input).unwrap();
+
+    // The missing 'in', then `index` left over where a field should be
+    assert_eq!(file.syntax_errors.len(), 2, "Got: {:?}", file.syntax_errors);
+    let error = &file.syntax_errors[0];
+    assert_eq!(error.message, "Expected 'in' after let binding");
+    assert_eq!(error.range.start, Position::new(1, 20));
+
+    // `lettuce` is an identifier, not `let tuce`
+    let value = get_first_field_value(parse_file("StateInfo:\n    cost = lettuce").unwrap());
+    assert!(matches!(&value.kind, ExprKind::Identifier(n) if n == "lettuce"));
+}
+
+#[test]
+fn test_nested_let_without_in_reports_missing_in() {
+    let input = "StateInfo:\n    cost = let a = 1 in a + (let b = 2)\n";
+    let file = parse_file(input).unwrap();
+
+    assert_eq!(file.syntax_errors.len(), 1, "Got: {:?}", file.syntax_errors);
+    assert_eq!(file.syntax_errors[0].range.start, Position::new(1, 38));
+
+    let file = parse_file("let total = let x = 5\n\nStateInfo:\n    cost = total\n").unwrap();
+    assert_eq!(file.bindings.len(), 1);
+    assert_eq!(file.syntax_errors.len(), 1, "Got: {:?}", file.syntax_errors);
+    assert_eq!(file.syntax_errors[0].range.start, Position::new(0, 21));
+}
+
+#[test]
+fn test_integer_literal_forms() {
+    for (source, expected) in [
+        ("42", 42),
+        ("0x1F", 31),
+        ("0XfF", 255),
+        ("0b1010", 10),
+        ("1_000", 1000),
+        ("0xFF_FF", 65535),
+        ("0b1111_0000", 240),
+    ] {
+        let value = parse_field_value(source);
+        assert!(
+            matches!(value.kind, ExprKind::IntLiteral(v) if v == expected),
+            "{} parsed as {:?}",
+            source,
+            value.kind
+        );
+        // Range covers the whole literal, prefix included
+        assert_eq!(value.range.start.character, 8);
+        assert_eq!(
+            value.range.end.character as usize,
+            8 + source.len(),
+            "Range of {}",
+            source
+        );
+    }
+}
+
+#[test]
+fn test_negative_prefixed_integer_literals() {
+    for (source, expected) in [("-0x10", 16), ("-0b11", 3), ("-1_000", 1000)] {
+        let value = parse_field_value(source);
+        let ExprKind::UnaryOp {
+            op: UnaryOperator::Neg,
+            operand,
+        } = &value.kind
+        else {
+            panic!("{} parsed as {:?}", source, value.kind);
+        };
+        assert!(matches!(operand.kind, ExprKind::IntLiteral(v) if v == expected));
+        assert_eq!(value.range.end.character as usize, 8 + source.len());
     }
 }
+
+#[test]
+fn test_negative_float_is_unary_neg() {
+    for source in ["-3.0", "- 3.0"] {
+        let value = parse_field_value(source);
+        let ExprKind::UnaryOp {
+            op: UnaryOperator::Neg,
+            operand,
+        } = &value.kind
+        else {
+            panic!("{} parsed as {:?}", source, value.kind);
+        };
+        assert!(matches!(operand.kind, ExprKind::FloatLiteral(v) if v == 3.0));
+        assert_eq!(value.range.start.character, 8);
+        assert_eq!(operand.range.end.character as usize, 8 + source.len());
+    }
+
+    // Subtraction is unaffected
+    let value = parse_field_value("1-2.5");
+    let (op, left, right) = binary_parts(&value);
+    assert_eq!(*op, BinaryOperator::Sub);
+    assert!(matches!(left.kind, ExprKind::IntLiteral(1)));
+    assert!(matches!(right.kind, ExprKind::FloatLiteral(v) if v == 2.5));
+}
+
+#[test]
+fn test_float_literals_still_parse() {
+    let value = parse_field_value("1.5e3");
+    assert!(matches!(value.kind, ExprKind::FloatLiteral(v) if v == 1500.0));
+
+    let value = parse_field_value("0.25");
+    assert!(matches!(value.kind, ExprKind::FloatLiteral(v) if v == 0.25));
+}
+
+#[test]
+fn test_deeply_nested_brackets_do_not_overflow() {
+    let depth = 5000;
+    let nested = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+    // As a field value: the expression depth guard rejects the field.
+    let input = format!("Architecture[\n    x = {}\n    y = 1\n]", nested);
+    let file = parse_file(&input).unwrap();
+    assert_eq!(file.blocks.len(), 1);
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    assert!(
+        items
+            .iter()
+            .all(|item| !matches!(item, BlockItem::Field(f) if f.key == "x"))
+    );
+
+    // Reasonable nesting is unaffected by the guard.
+    let input = format!(
+        "Architecture[\n    x = {}1{}\n]",
+        "[".repeat(20),
+        "]".repeat(20)
+    );
+    let value = get_first_field_value(parse_file(&input).unwrap());
+    assert!(matches!(value.kind, ExprKind::List(_)));
+
+    // As a block body: the bracket scanner is iterative.
+    let input = format!("Architecture{}\nRouteInfo:\n    routed_gates = CX", nested);
+    let file = parse_file(&input).unwrap();
+    assert!(file.blocks.iter().any(|b| b.kind == "RouteInfo"));
+}
+
+#[test]
+fn test_trailing_comma_in_list_and_tuple() {
+    let value = parse_field_value("[1, 2, 3,]");
+    assert!(matches!(value.kind, ExprKind::List(ref items) if items.len() == 3));
+    assert_eq!(value.range.end.character, 18);
+
+    let value = parse_field_value("(1, 2, )");
+    assert!(matches!(value.kind, ExprKind::Tuple(ref items) if items.len() == 2));
+    assert_eq!(value.range.end.character, 16);
+
+    // A trailing comma turns a parenthesized expression into a one-element tuple
+    let value = parse_field_value("(1,)");
+    assert!(matches!(value.kind, ExprKind::Tuple(ref items) if items.len() == 1));
+    let value = parse_field_value("(1)");
+    assert!(matches!(value.kind, ExprKind::IntLiteral(1)));
+}
+
+#[test]
+fn test_trailing_comma_in_struct_literal_and_call() {
+    let value = parse_field_value("Transition{edge = e, cost = 1,}");
+    assert!(matches!(
+        value.kind,
+        ExprKind::StructLiteral { ref fields, .. } if fields.len() == 2
+    ));
+    assert_eq!(value.range.end.character, 39);
+
+    let value = parse_field_value("value_swap(\n        a,\n        b,\n    )");
+    assert!(matches!(
+        value.kind,
+        ExprKind::FunctionCall { ref args, .. } if args.len() == 2
+    ));
+    assert_eq!(value.range.end.line, 4);
+    assert_eq!(value.range.end.character, 5);
+}
+
+#[test]
+fn test_lone_comma_is_rejected() {
+    let input = "StateInfo:\n    x = [,]\n    y = 1\n";
+    let file = parse_file(input).unwrap();
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    assert_eq!(items.len(), 1, "Only 'y' should parse");
+}
+
+#[test]
+fn test_parse_error_display() {
+    let error = ParseError {
+        message: "Unexpected input".to_string(),
+        offset: 12,
+        range: Range {
+            start: Position::new(1, 4),
+            end: Position::new(1, 5),
+        },
+    };
+
+    assert_eq!(error.to_string(), "Unexpected input at line 2, column 5");
+}
+
+#[test]
+fn test_block_outer_range() {
+    let input =
+        "RouteInfo:\n    routed_gates = CX\n    realize_gate = []\n\n\nArch[\n    name = 'a'\n]\n";
+    let file = parse_file(input).unwrap();
+    assert_eq!(file.blocks.len(), 2);
+
+    let colon = &file.blocks[0].outer_range;
+    assert_eq!(colon.start, Position::new(0, 0));
+    assert_eq!(colon.end, Position::new(2, 21));
+
+    let bracket = &file.blocks[1].outer_range;
+    assert_eq!(bracket.start, Position::new(5, 0));
+    assert_eq!(bracket.end, Position::new(7, 1));
+}
+
+#[test]
+fn test_top_level_rust_blocks_recorded() {
+    let input = "{{\nfn a() {}\n}}\n// comment\n{{ fn b() {} }}\nArch[name = 'a']\n";
+    let file = parse_file(input).unwrap();
+
+    assert_eq!(file.blocks.len(), 1);
+    assert_eq!(file.rust_blocks.len(), 2);
+
+    let first = &file.rust_blocks[0];
+    assert_eq!(first.code, "\nfn a() {}\n");
+    assert_eq!(first.range.start, Position::new(0, 2));
+    assert_eq!(first.range.end, Position::new(2, 0));
+
+    let second = &file.rust_blocks[1];
+    assert_eq!(second.code, " fn b() {} ");
+    assert_eq!(second.range.start, Position::new(4, 2));
+    assert_eq!(second.range.end, Position::new(4, 13));
+}
+
+#[test]
+fn test_parse_type() {
+    let parsed = |input: &str| parse_type(input).unwrap().1;
+
+    assert_eq!(parsed("Int"), Type::Int);
+    assert_eq!(parsed("Float"), Type::Float);
+    assert_eq!(parsed("Bool"), Type::Bool);
+    assert_eq!(parsed("String"), Type::String);
+    assert_eq!(parsed("Qubit"), Type::Qubit);
+    assert_eq!(parsed("Vec<Location>"), Type::Vec(Box::new(Type::Location)));
+    assert_eq!(parsed("Option<Qubit>"), Type::Option(Box::new(Type::Qubit)));
+    assert_eq!(
+        parsed("(Location, Location)"),
+        Type::Tuple(vec![Type::Location, Type::Location])
+    );
+    assert_eq!(
+        parsed("Vec<(Int, Option<Float>)>"),
+        Type::Vec(Box::new(Type::Tuple(vec![
+            Type::Int,
+            Type::Option(Box::new(Type::Float))
+        ])))
+    );
+    assert_eq!(parsed("Mystery"), Type::Unknown);
+}
+
+// Operator Precedence & Associativity Tests
+
+fn binary_parts(expr: &Expr) -> (&BinaryOperator, &Expr, &Expr) {
+    match &expr.kind {
+        ExprKind::BinaryOp { op, left, right } => (op, left, right),
+        other => panic!("Expected binary op, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_subtraction_is_left_associative() {
+    // a - b - c == (a - b) - c
+    let value = parse_field_value("a - b - c");
+    let (op, left, right) = binary_parts(&value);
+    assert_eq!(*op, BinaryOperator::Sub);
+    assert!(matches!(&right.kind, ExprKind::Identifier(n) if n == "c"));
+
+    let (inner_op, inner_left, inner_right) = binary_parts(left);
+    assert_eq!(*inner_op, BinaryOperator::Sub);
+    assert!(matches!(&inner_left.kind, ExprKind::Identifier(n) if n == "a"));
+    assert!(matches!(&inner_right.kind, ExprKind::Identifier(n) if n == "b"));
+
+    // Each node spans only its own operands
+    assert_eq!(value.range.start.character, 8);
+    assert_eq!(value.range.end.character, 17);
+    assert_eq!(left.range.start.character, 8);
+    assert_eq!(left.range.end.character, 13);
+}
+
+#[test]
+fn test_multiplication_binds_tighter_than_addition() {
+    let value = parse_field_value("2 + 3 * 4");
+    let (op, left, right) = binary_parts(&value);
+    assert_eq!(*op, BinaryOperator::Add);
+    assert!(matches!(left.kind, ExprKind::IntLiteral(2)));
+
+    let (inner_op, inner_left, inner_right) = binary_parts(right);
+    assert_eq!(*inner_op, BinaryOperator::Mul);
+    assert!(matches!(inner_left.kind, ExprKind::IntLiteral(3)));
+    assert!(matches!(inner_right.kind, ExprKind::IntLiteral(4)));
+    assert_eq!(right.range.start.character, 12);
+
+    let value = parse_field_value("2 * 3 + 4");
+    let (op, left, _) = binary_parts(&value);
+    assert_eq!(*op, BinaryOperator::Add);
+    assert_eq!(*binary_parts(left).0, BinaryOperator::Mul);
+}
+
+#[test]
+fn test_power_is_right_associative_and_binds_tighter_than_mul() {
+    // 2 ** 3 ** 2 == 2 ** (3 ** 2)
+    let value = parse_field_value("2 ** 3 ** 2");
+    let (op, left, right) = binary_parts(&value);
+    assert_eq!(*op, BinaryOperator::Pow);
+    assert!(matches!(left.kind, ExprKind::IntLiteral(2)));
+    let (inner_op, inner_left, inner_right) = binary_parts(right);
+    assert_eq!(*inner_op, BinaryOperator::Pow);
+    assert!(matches!(inner_left.kind, ExprKind::IntLiteral(3)));
+    assert!(matches!(inner_right.kind, ExprKind::IntLiteral(2)));
+
+    // 4 * 3 ** 2 == 4 * (3 ** 2)
+    let value = parse_field_value("4 * 3 ** 2");
+    let (op, left, right) = binary_parts(&value);
+    assert_eq!(*op, BinaryOperator::Mul);
+    assert!(matches!(left.kind, ExprKind::IntLiteral(4)));
+    assert_eq!(*binary_parts(right).0, BinaryOperator::Pow);
+    assert_eq!(right.range.start.character, 12);
+
+    // 3 ** 2 * 4 == (3 ** 2) * 4
+    let value = parse_field_value("3 ** 2 * 4");
+    let (op, left, _) = binary_parts(&value);
+    assert_eq!(*op, BinaryOperator::Mul);
+    assert_eq!(*binary_parts(left).0, BinaryOperator::Pow);
+}
+
+#[test]
+fn test_raw_identifier_field_name() {
+    let input = "StateInfo:\n    `then` = 5\n";
+    let file = parse_file(input).unwrap();
+    assert!(file.syntax_errors.is_empty(), "{:?}", file.syntax_errors);
+
+    let field = file.blocks[0].field("then").expect("escaped field");
+    assert_eq!(
+        field.key_range,
+        Range::new(Position::new(1, 4), Position::new(1, 10))
+    );
+    assert!(matches!(field.value.kind, ExprKind::IntLiteral(5)));
+
+    // Unescaped, the keyword is still rejected
+    let file = parse_file("StateInfo:\n    then = 5\n").unwrap();
+    assert_eq!(file.syntax_errors.len(), 1);
+}
+
+#[test]
+fn test_raw_identifier_expressions() {
+    let input = "StateInfo:\n    cost = let `in` = 1 in `in` + x.`then`\n";
+    let file = parse_file(input).unwrap();
+    assert!(file.syntax_errors.is_empty(), "{:?}", file.syntax_errors);
+
+    let ExprKind::LetBinding {
+        name,
+        name_range,
+        body,
+        ..
+    } = &get_first_field_value(file).kind
+    else {
+        panic!("Expected let binding");
+    };
+    assert_eq!(name, "in");
+    assert_eq!(
+        *name_range,
+        Range::new(Position::new(1, 15), Position::new(1, 19))
+    );
+    let ExprKind::BinaryOp { left, right, .. } = &body.kind else {
+        panic!("Expected binary op");
+    };
+    assert!(matches!(&left.kind, ExprKind::Identifier(n) if n == "in"));
+    assert_eq!(left.range.start, Position::new(1, 27));
+    assert!(matches!(&right.kind, ExprKind::FieldAccess { field, .. } if field == "then"));
+}
+
+#[test]
+fn test_node_at_position_finds_innermost_expression() {
+    let input = "let n = 1 + 2\n\nStateInfo:\n    cost = Arch.edges.first.x\n";
+    let file = parse_file(input).unwrap();
+    let field_name = |pos| match node_at_position(&file, pos).map(|e| &e.kind) {
+        Some(ExprKind::FieldAccess { field, .. }) => field.clone(),
+        Some(ExprKind::Identifier(name)) => name.clone(),
+        other => panic!("Unexpected node {:?}", other),
+    };
+
+    assert_eq!(field_name(Position::new(3, 12)), "Arch");
+    assert_eq!(field_name(Position::new(3, 17)), "edges");
+    assert_eq!(field_name(Position::new(3, 23)), "first");
+    assert_eq!(field_name(Position::new(3, 28)), "x");
+
+    let node = node_at_position(&file, Position::new(0, 12)).unwrap();
+    assert!(matches!(node.kind, ExprKind::IntLiteral(2)));
+
+    // On the key rather than the value, or outside any block
+    assert!(node_at_position(&file, Position::new(3, 5)).is_none());
+    assert!(node_at_position(&file, Position::new(1, 0)).is_none());
+
+    assert_eq!(
+        block_at_position(&file, Position::new(3, 5)).map(|b| b.kind.as_str()),
+        Some("StateInfo")
+    );
+    assert!(block_at_position(&file, Position::new(0, 5)).is_none());
+}
+
+#[test]
+fn test_expression_ranges_end_at_last_token() {
+    let input = "StateInfo:\n    cost = foo(a, [1, 2] )  // note\n    other = Point{x = 1}   \n    flag = if a then 1 else 2  \n";
+    let file = parse_file(input).unwrap();
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    let values: Vec<_> = items
+        .iter()
+        .map(|item| match item {
+            BlockItem::Field(field) => &field.value,
+            other => panic!("Expected field, got {:?}", other),
+        })
+        .collect();
+
+    assert_eq!(values[0].range.end, Position::new(1, 26));
+    let ExprKind::FunctionCall { args, .. } = &values[0].kind else {
+        panic!("Expected call");
+    };
+    assert_eq!(args[1].range.end, Position::new(1, 24));
+    assert_eq!(values[1].range.end, Position::new(2, 24));
+    assert_eq!(values[2].range.end, Position::new(3, 29));
+
+    // The whitespace after a value belongs to no node
+    assert!(node_at_position(&file, Position::new(1, 28)).is_none());
+    assert!(node_at_position(&file, Position::new(2, 26)).is_none());
+}
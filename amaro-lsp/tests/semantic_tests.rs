@@ -1,6 +1,13 @@
 use amaro_lsp::ast::*;
-use amaro_lsp::parser::{check_semantics, parse_file};
-use tower_lsp::lsp_types::DiagnosticSeverity;
+use amaro_lsp::parser::config::rule_code;
+use amaro_lsp::parser::symbols::{SymbolTable, Type};
+use amaro_lsp::parser::{
+    AnalysisConfig, KNOWN_BLOCKS, RuleSeverity, check_semantics, check_semantics_with_config,
+    diagnose_parsed, diagnose_with_config, infer_expr_type, parse_file, parse_file_with_config,
+};
+use amaro_lsp::server::InitializationOptions;
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
 
 const MOCK_MANDATORY_BLOCKS: &str = r#"
 RouteInfo:
@@ -55,7 +62,7 @@ fn no_warning_for_correct_capitalization() {
 fn test_all_valid_no_errors() {
     let input = MOCK_MANDATORY_BLOCKS;
 
-    let file = parse_file(&input).unwrap();
+    let file = parse_file(input).unwrap();
     let diags = check_semantics(&file);
 
     assert!(
@@ -154,7 +161,7 @@ TransitionInfo:
     apply = identity
 "#;
 
-    let file = parse_file(&input).unwrap();
+    let file = parse_file(input).unwrap();
     let diags = check_semantics(&file);
 
     let errors: Vec<_> = diags
@@ -259,16 +266,15 @@ TransitionInfo:
     let file = parse_file(input).unwrap();
     let diags = check_semantics(&file);
 
-    let warnings: Vec<_> = diags
+    let unknown: Vec<_> = diags
         .iter()
         .filter(|d| {
-            d.severity == Some(DiagnosticSeverity::WARNING)
-                && d.message.contains("not a recognized standard gate")
+            d.severity == Some(DiagnosticSeverity::ERROR) && d.message.contains("Unknown gate")
         })
         .collect();
 
-    assert_eq!(warnings.len(), 1, "Should warn about InvalidGate");
-    assert!(warnings[0].message.contains("InvalidGate"));
+    assert_eq!(unknown.len(), 1, "Should report InvalidGate");
+    assert_eq!(unknown[0].message, "Unknown gate 'InvalidGate'.");
 
     let errors: Vec<_> = diags
         .iter()
@@ -322,27 +328,20 @@ TransitionInfo:
     let file = parse_file(input).unwrap();
     let diags = check_semantics(&file);
 
-    let warnings: Vec<_> = diags
+    let unknown: Vec<_> = diags
         .iter()
-        .filter(|d| d.severity == Some(DiagnosticSeverity::WARNING))
+        .filter(|d| d.message.contains("Unknown gate"))
         .collect();
 
-    assert_eq!(warnings.len(), 1, "Should warn only about BadGate");
-    assert!(warnings[0].message.contains("BadGate"));
+    assert_eq!(unknown.len(), 1, "Should report only BadGate");
+    assert!(unknown[0].message.contains("BadGate"));
 
-    let errors: Vec<_> = diags
-        .iter()
-        .filter(|d| d.severity == Some(DiagnosticSeverity::ERROR))
-        .collect();
-    assert!(
-        !errors.is_empty(),
-        "Should have errors because BadGate is undefined"
-    );
+    assert_eq!(unknown[0].severity, Some(DiagnosticSeverity::ERROR));
     assert!(
-        errors
+        !diags
             .iter()
-            .any(|d| d.message.contains("Undefined variable")),
-        "Should report BadGate as undefined"
+            .any(|d| d.message.contains("Undefined variable 'BadGate'")),
+        "Unknown gate replaces the generic undefined-variable error"
     );
 }
 
@@ -655,6 +654,52 @@ TransitionInfo:
     assert!(undefined_errors.is_empty(), "Let binding should work");
 }
 
+#[test]
+fn test_undefined_variable_range() {
+    let input = format!(
+        "{}StateInfo:\n    cost = missing_name\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+
+    let file = parse_file(&input).unwrap();
+    let diags = check_semantics(&file);
+
+    let undefined: Vec<_> = diags
+        .iter()
+        .filter(|d| d.message.contains("Undefined variable 'missing_name'"))
+        .collect();
+    assert_eq!(undefined.len(), 1, "Got: {:?}", diags);
+    assert_eq!(undefined[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(undefined[0].range.start.character, 11);
+    assert_eq!(undefined[0].range.end.character, 23);
+}
+
+#[test]
+fn test_scoped_names_do_not_leak() {
+    let input = format!(
+        "{}StateInfo:\n    cost = ((let temp = 1 in temp), map(|item| -> item, [1]), temp, item)\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+
+    let file = parse_file(&input).unwrap();
+    let diags = check_semantics(&file);
+
+    for name in ["temp", "item"] {
+        let count = diags
+            .iter()
+            .filter(|d| {
+                d.message
+                    .contains(&format!("Undefined variable '{}'", name))
+            })
+            .count();
+        assert_eq!(
+            count, 1,
+            "'{}' should only be undefined outside its scope",
+            name
+        );
+    }
+}
+
 // State.map[Gate.qubits[0]] - QubitMap indexed by Qubit
 #[test]
 fn test_qubit_index_on_qubitmap() {
@@ -785,3 +830,1335 @@ TransitionInfo:
         errors
     );
 }
+
+// Type Inference Tests
+
+fn infer(source: &str) -> Type {
+    let input = format!("StateInfo:\n    value = {}", source);
+    let file = parse_file(&input).unwrap();
+    let BlockContent::Fields(items) = &file.blocks[0].content;
+    let BlockItem::Field(field) = &items[0] else {
+        panic!("Expected field");
+    };
+
+    let mut diagnostics = Vec::new();
+    infer_expr_type(&field.value, &mut SymbolTable::new(), &mut diagnostics)
+}
+
+#[test]
+fn test_infer_literals_and_containers() {
+    assert_eq!(infer("1"), Type::Int);
+    assert_eq!(infer("1.5"), Type::Float);
+    assert_eq!(infer("true"), Type::Bool);
+    assert_eq!(infer("'x'"), Type::String);
+    assert_eq!(infer("[1, 2]"), Type::Vec(Box::new(Type::Int)));
+    assert_eq!(infer("(1, true)"), Type::Tuple(vec![Type::Int, Type::Bool]));
+    assert_eq!(infer("Some(1)"), Type::Option(Box::new(Type::Int)));
+    assert_eq!(infer("(1, true).(1)"), Type::Bool);
+}
+
+#[test]
+fn test_infer_binary_and_unary_ops() {
+    assert_eq!(infer("1 + 2"), Type::Int);
+    assert_eq!(infer("1 * 2.0"), Type::Float);
+    assert_eq!(infer("-1.0"), Type::Float);
+    assert_eq!(infer("1 < 2"), Type::Bool);
+    assert_eq!(infer("true && false"), Type::Bool);
+    assert_eq!(infer("!true"), Type::Bool);
+    assert_eq!(infer("0..4"), Type::Vec(Box::new(Type::Int)));
+    // Non-numeric arithmetic stays permissive
+    assert_eq!(infer("'a' + 'b'"), Type::Unknown);
+}
+
+#[test]
+fn test_infer_function_call_return_type() {
+    assert_eq!(
+        infer("horizontal_neighbors(Location(0), 1)"),
+        Type::Vec(Box::new(Type::Location))
+    );
+}
+
+#[test]
+fn test_binary_op_operands_are_checked() {
+    let input = format!(
+        "{}StateInfo:\n    cost = 1 + missing\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+    let file = parse_file(&input).unwrap();
+    let diags = check_semantics(&file);
+    assert!(
+        diags
+            .iter()
+            .any(|d| d.message.contains("Undefined variable 'missing'")),
+        "Got: {:?}",
+        diags
+    );
+}
+
+// Cost Type Tests
+
+fn cost_errors(cost: &str) -> Vec<String> {
+    let input = format!(
+        "RouteInfo:\n    routed_gates = CX\n    realize_gate = []\nTransitionInfo:\n    apply = []\n    get_transitions = []\n    cost = {}\n",
+        cost
+    );
+    let file = parse_file(&input).unwrap();
+    check_semantics(&file)
+        .into_iter()
+        .filter(|d| d.message.contains("'cost' must return Float"))
+        .map(|d| d.message)
+        .collect()
+}
+
+#[test]
+fn test_cost_bool_is_error() {
+    assert_eq!(
+        cost_errors("(1 > 0)"),
+        vec!["'cost' must return Float, got Bool.".to_string()]
+    );
+    assert_eq!(cost_errors("'high'").len(), 1);
+    assert_eq!(
+        cost_errors("[1.0]"),
+        vec!["'cost' must return Float, got Vec<Float>.".to_string()]
+    );
+}
+
+#[test]
+fn test_cost_numeric_is_ok() {
+    assert!(cost_errors("0.0").is_empty());
+    assert!(cost_errors("1").is_empty());
+    assert!(cost_errors("1 + 2.5").is_empty());
+    // Unknown stays permissive
+    assert!(cost_errors("Arch.implementation").is_empty());
+}
+
+#[test]
+fn test_cost_error_range_is_value() {
+    let input = format!("{}StateInfo:\n    cost = true\n", MOCK_MANDATORY_BLOCKS);
+    let file = parse_file(&input).unwrap();
+    let diag = check_semantics(&file)
+        .into_iter()
+        .find(|d| d.message.contains("'cost' must return Float"))
+        .expect("Bool cost in StateInfo should error");
+    assert_eq!(diag.severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(diag.range.start.character, 11);
+    assert_eq!(diag.range.end.character, 15);
+}
+
+// Conditional Tests
+
+fn state_cost_diags(cost: &str) -> Vec<Diagnostic> {
+    let input = format!("{}StateInfo:\n    cost = {}\n", MOCK_MANDATORY_BLOCKS, cost);
+    let file = parse_file(&input).unwrap();
+    check_semantics(&file)
+}
+
+/// The diagnostics for `cost` whose code is `code`. Rule diagnostics carry
+/// their `rule_code`; errors outside the configurable rules have none.
+fn cost_diags_with(cost: &str, code: Option<NumberOrString>) -> Vec<Diagnostic> {
+    state_cost_diags(cost)
+        .into_iter()
+        .filter(|d| d.code == code)
+        .collect()
+}
+
+#[test]
+fn test_if_condition_must_be_bool() {
+    let diags = state_cost_diags("if step then 1.0 else 0.0");
+    let diag = diags
+        .iter()
+        .find(|d| d.message.contains("If-condition must be Bool"))
+        .expect("Non-Bool condition should error");
+    assert_eq!(diag.message, "If-condition must be Bool, got Int.");
+    assert_eq!(diag.severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(diag.range.start.line, 10);
+    assert_eq!(diag.range.start.character, 14);
+}
+
+#[test]
+fn test_if_condition_bool_or_unknown_is_ok() {
+    for cond in ["1 < 2", "Arch.implementation"] {
+        let diags = state_cost_diags(&format!("if {} then 1.0 else 0.0", cond));
+        assert!(
+            !diags.iter().any(|d| d.message.contains("If-condition")),
+            "'{}' should be accepted. Got: {:?}",
+            cond,
+            diags
+        );
+    }
+}
+
+#[test]
+fn test_if_branch_mismatch_is_warning() {
+    let diags = state_cost_diags("if true then 1.0 else 'none'");
+    let diag = diags
+        .iter()
+        .find(|d| d.message.contains("incompatible types"))
+        .expect("Mismatched branches should warn");
+    assert_eq!(diag.severity, Some(DiagnosticSeverity::WARNING));
+
+    let diags = state_cost_diags("if true then 1 else 0.5");
+    assert!(
+        !diags
+            .iter()
+            .any(|d| d.message.contains("incompatible types"))
+    );
+}
+
+#[test]
+fn test_literal_condition_marks_dead_branch() {
+    let diags = cost_diags_with("if true then 1.0 else 0.0", rule_code("unreachableBranch"));
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(
+        diags[0].message,
+        "else-branch is unreachable (condition is always true)."
+    );
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(diags[0].range.start.character, 33);
+
+    let diags = cost_diags_with("if false then 1.0 else 0.0", rule_code("unreachableBranch"));
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(
+        diags[0].message,
+        "then-branch is unreachable (condition is always false)."
+    );
+    assert_eq!(diags[0].range.start.character, 25);
+
+    assert!(
+        cost_diags_with("if 1 < 2 then 1.0 else 0.0", rule_code("unreachableBranch")).is_empty()
+    );
+}
+
+#[test]
+fn test_literal_condition_infers_taken_branch() {
+    assert_eq!(infer("if true then 1 else 'none'"), Type::Int);
+    assert_eq!(infer("if false then 1 else 'none'"), Type::String);
+}
+
+// Arity Tests
+
+#[test]
+fn test_arity_mismatch_names_callee() {
+    let diags = state_cost_diags("value_swap(Location(0), Location(1), Location(2))");
+    let diag = diags
+        .iter()
+        .find(|d| d.message.contains("expects"))
+        .expect("Wrong arity should error");
+    assert_eq!(diag.message, "value_swap expects 2 arguments, got 3.");
+    assert_eq!(diag.severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(diag.range.start.character, 11);
+
+    let diags = state_cost_diags("Location()");
+    assert!(
+        diags
+            .iter()
+            .any(|d| d.message == "Location expects 1 argument, got 0."),
+        "Got: {:?}",
+        diags
+    );
+}
+
+#[test]
+fn test_lambda_params_typed_from_call() {
+    assert_eq!(
+        infer("map(|x| -> x, Vec(Location(0)))"),
+        Type::Vec(Box::new(Type::Location))
+    );
+    assert_eq!(
+        infer("map(|x| -> x < 2, Vec(1, 2))"),
+        Type::Vec(Box::new(Type::Bool))
+    );
+    assert_eq!(infer("fold(0, |x, acc| -> acc + x, Vec(1, 2))"), Type::Int);
+    assert_eq!(
+        infer("|x| -> x"),
+        Type::Function {
+            params: vec![Type::Unknown],
+            return_type: Box::new(Type::Unknown),
+            variadic: false,
+        }
+    );
+
+    // The element type reaches uses inside the body
+    let diags = state_cost_diags("fold(0.0, |x, acc| -> acc + x, Vec('a'))");
+    assert!(
+        diags.iter().any(|d| d.message.contains("String")),
+        "Got: {:?}",
+        diags
+    );
+}
+
+#[test]
+fn test_map_and_fold_lambda_arity() {
+    let diags = cost_diags_with("map(|x, y| -> x, Vec(1.0)).len()", None);
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(
+        diags[0].message,
+        "map expects a 1-argument function, got 2."
+    );
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(diags[0].range.start.character, 15);
+
+    let diags = cost_diags_with("fold(0.0, |x| -> x, Vec(1.0))", None);
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(
+        diags[0].message,
+        "fold expects a 2-argument function, got 1."
+    );
+
+    assert!(cost_diags_with("map(|x| -> x, Vec(1.0)).len()", None).is_empty());
+    assert!(cost_diags_with("fold(0.0, |x, acc| -> acc + x, Vec(1.0))", None).is_empty());
+}
+
+#[test]
+fn test_arity_mismatch_still_checks_arguments() {
+    let diags = state_cost_diags("value_swap(missing)");
+    assert!(
+        diags
+            .iter()
+            .any(|d| d.message.contains("expects 2 arguments"))
+    );
+    assert!(
+        diags
+            .iter()
+            .any(|d| d.message.contains("Undefined variable 'missing'"))
+    );
+}
+
+#[test]
+fn test_variadic_vec_constructor() {
+    for call in ["Vec()", "Vec(1)", "Vec(1, 2, 3)"] {
+        let diags = state_cost_diags(&format!("({}).len()", call));
+        assert!(
+            !diags.iter().any(|d| d.message.contains("expects")),
+            "'{}' should accept any arity. Got: {:?}",
+            call,
+            diags
+        );
+    }
+}
+
+#[test]
+fn test_numeric_builtins_type_check() {
+    let diags = state_cost_diags("max(abs(1), 2.5, dist(Location(0), Location(1)))");
+    let errors: Vec<_> = diags
+        .iter()
+        .filter(|d| d.severity == Some(DiagnosticSeverity::ERROR))
+        .collect();
+    assert!(errors.is_empty(), "Got: {:?}", errors);
+}
+
+#[test]
+fn test_max_min_return_argument_type() {
+    assert_eq!(infer("max(1, 2)"), Type::Int);
+    assert_eq!(infer("min(3)"), Type::Int);
+    assert_eq!(infer("max(1, 2.5)"), Type::Float);
+    assert_eq!(infer("min(1.5, 2.5)"), Type::Float);
+}
+
+// Vec Method Tests
+
+#[test]
+fn test_vec_method_types() {
+    let v = "[Location(0)]";
+    assert_eq!(
+        infer(&format!("{}.push(Location(1))", v)),
+        Type::Vec(Box::new(Type::Location))
+    );
+    assert_eq!(
+        infer(&format!("{}.extend({})", v, v)),
+        Type::Vec(Box::new(Type::Location))
+    );
+    assert_eq!(
+        infer(&format!("{}.pop()", v)),
+        Type::Option(Box::new(Type::Location))
+    );
+    assert_eq!(infer(&format!("{}.len()", v)), Type::Int);
+    assert_eq!(infer(&format!("{}.contains(Location(1))", v)), Type::Bool);
+    assert_eq!(infer(&format!("{}.is_empty()", v)), Type::Bool);
+}
+
+#[test]
+fn test_vec_method_chain() {
+    assert_eq!(infer("[1].push(2).extend([3]).len()"), Type::Int);
+
+    let diags = state_cost_diags("[1, 2].push(3).len()");
+    let errors: Vec<_> = diags
+        .iter()
+        .filter(|d| d.severity == Some(DiagnosticSeverity::ERROR))
+        .collect();
+    assert!(errors.is_empty(), "Got: {:?}", errors);
+}
+
+#[test]
+fn test_top_level_let_visible_in_blocks() {
+    let input = format!(
+        "let base_cost = 2\nlet end_cost = base_cost * 1.5\n{}StateInfo:\n    cost = end_cost\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+
+    let file = parse_file(&input).unwrap();
+    let diags = check_semantics(&file);
+
+    let errors: Vec<_> = diags
+        .iter()
+        .filter(|d| d.severity == Some(DiagnosticSeverity::ERROR))
+        .collect();
+    assert!(errors.is_empty(), "Got: {:?}", errors);
+}
+
+#[test]
+fn test_unknown_block_kind_warning() {
+    let input = format!("{}FooInfo:\n    x = 1\n", MOCK_MANDATORY_BLOCKS);
+
+    let file = parse_file(&input).unwrap();
+    let diags = check_semantics(&file);
+
+    let unknown: Vec<_> = diags
+        .iter()
+        .filter(|d| d.message.starts_with("Unknown block kind"))
+        .collect();
+    assert_eq!(unknown.len(), 1, "Got: {:?}", diags);
+    assert_eq!(unknown[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert!(
+        unknown[0]
+            .message
+            .contains("'FooInfo'. Expected one of: GateRealization, Transition,")
+    );
+
+    let fooinfo = file.blocks.iter().find(|b| b.kind == "FooInfo").unwrap();
+    assert_eq!(unknown[0].range, fooinfo.range);
+}
+
+#[test]
+fn test_known_block_kinds_not_flagged() {
+    let input = format!("{}architecture[name='test']", MOCK_MANDATORY_BLOCKS);
+
+    let file = parse_file(&input).unwrap();
+    let diags = check_semantics(&file);
+
+    assert!(
+        !diags
+            .iter()
+            .any(|d| d.message.starts_with("Unknown block kind")),
+        "Got: {:?}",
+        diags
+    );
+}
+
+#[test]
+fn test_duplicate_field_error() {
+    let input = r#"
+RouteInfo:
+    routed_gates = CX
+    realize_gate = []
+
+TransitionInfo:
+    cost = 1.0
+    apply = []
+    get_transitions = []
+    cost = 2.0
+"#;
+
+    let uri = tower_lsp::lsp_types::Url::parse("file:///ws/routes.qmrl").unwrap();
+    let config = AnalysisConfig::default().for_document(&uri);
+    let diags = diagnose_with_config(input, &config);
+
+    let dup: Vec<_> = diags
+        .iter()
+        .filter(|d| d.message == "Duplicate field 'cost' in TransitionInfo")
+        .collect();
+    assert_eq!(dup.len(), 1, "Got: {:?}", diags);
+    assert_eq!(dup[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(dup[0].range.start.line, 9);
+    assert_eq!(dup[0].range.start.character, 4);
+
+    let related = dup[0].related_information.as_ref().unwrap();
+    assert_eq!(related[0].location.uri, uri);
+    assert_eq!(related[0].location.range.start.line, 6);
+}
+
+#[test]
+fn test_duplicate_block_links_first_definition() {
+    let input = format!(
+        "{}
+StateInfo:
+    cost = 1.0
+stateinfo:
+    cost = 2.0
+",
+        MOCK_MANDATORY_BLOCKS
+    );
+    let duplicate = |config: &AnalysisConfig| {
+        diagnose_with_config(&input, config)
+            .into_iter()
+            .find(|d| d.message == "Duplicate definition of 'stateinfo' block.")
+            .expect("Duplicate block should error")
+    };
+
+    let uri = tower_lsp::lsp_types::Url::parse("file:///ws/main.qmrl").unwrap();
+    let diag = duplicate(&AnalysisConfig::default().for_document(&uri));
+    assert_eq!(diag.range.start.line, 12);
+    let related = diag.related_information.unwrap();
+    assert_eq!(related.len(), 1);
+    assert_eq!(related[0].location.uri, uri);
+    assert_eq!(related[0].location.range.start.line, 10);
+    assert_eq!(related[0].message, "First defined here");
+
+    // Without a document there is nowhere to link to
+    assert!(
+        duplicate(&AnalysisConfig::default())
+            .related_information
+            .is_none()
+    );
+}
+
+#[test]
+fn test_diagnose_combines_syntax_and_semantic_diagnostics() {
+    let input = format!(
+        "{}StateInfo:\n    name = 'unterminated\n    cost = missing_name\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+
+    let diags = amaro_lsp::diagnose(&input);
+
+    assert!(
+        diags
+            .iter()
+            .any(|d| d.message.contains("Unterminated string literal")),
+        "Got: {:?}",
+        diags
+    );
+    assert!(
+        diags
+            .iter()
+            .any(|d| d.message.contains("Undefined variable 'missing_name'")),
+        "Got: {:?}",
+        diags
+    );
+}
+
+#[test]
+fn test_diagnose_clean_source() {
+    assert!(amaro_lsp::diagnose(MOCK_MANDATORY_BLOCKS).is_empty());
+}
+
+#[test]
+fn test_custom_known_blocks() {
+    let config = AnalysisConfig {
+        known_blocks: vec![
+            "RouteInfo".to_string(),
+            "TransitionInfo".to_string(),
+            "DialectInfo".to_string(),
+        ],
+        ..AnalysisConfig::default()
+    };
+    let input = format!(
+        "{}dialectinfo:\n    x = 1\nStateInfo:\n    cost = 1.0\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+
+    let diags = diagnose_with_config(&input, &config);
+
+    assert!(
+        diags
+            .iter()
+            .any(|d| d.message.contains("'dialectinfo' should be Capitalized")),
+        "Got: {:?}",
+        diags
+    );
+    let unknown: Vec<_> = diags
+        .iter()
+        .filter(|d| d.message.starts_with("Unknown block kind"))
+        .collect();
+    assert_eq!(unknown.len(), 1, "Got: {:?}", diags);
+    assert!(unknown[0].message.contains("'StateInfo'"));
+    assert!(
+        unknown[0]
+            .message
+            .contains("Expected one of: RouteInfo, TransitionInfo, DialectInfo.")
+    );
+}
+
+#[test]
+fn test_initialization_options_known_blocks() {
+    let options = InitializationOptions::from_value(Some(serde_json::json!({
+        "knownBlocks": ["RouteInfo", "DialectInfo"]
+    })));
+    assert_eq!(
+        options.into_config().known_blocks,
+        vec!["RouteInfo".to_string(), "DialectInfo".to_string()]
+    );
+
+    let defaults = InitializationOptions::from_value(None).into_config();
+    assert_eq!(defaults.known_blocks.len(), KNOWN_BLOCKS.len());
+
+    let malformed = InitializationOptions::from_value(Some(serde_json::json!({
+        "knownBlocks": "RouteInfo"
+    })));
+    assert!(malformed.known_blocks.is_none());
+    assert_eq!(malformed.warnings.len(), 1);
+}
+
+#[test]
+fn test_malformed_initialization_option_keeps_the_others() {
+    let options = InitializationOptions::from_value(Some(serde_json::json!({
+        "knownBlocks": ["MyInfo"],
+        "maxFileBytes": "big",
+        "debugAst": true,
+        "colour": "red"
+    })));
+    assert!(options.debug_ast);
+    assert_eq!(options.warnings.len(), 2, "Got: {:?}", options.warnings);
+    assert_eq!(
+        options.warnings[0],
+        "Ignoring unknown initialization option 'colour'."
+    );
+    assert!(options.warnings[1].contains("'maxFileBytes'"));
+
+    let config = options.into_config();
+    assert_eq!(config.known_blocks, vec!["MyInfo".to_string()]);
+    assert_eq!(
+        config.max_file_bytes,
+        AnalysisConfig::default().max_file_bytes
+    );
+}
+
+#[test]
+fn test_initialization_options_debug_ast() {
+    assert!(!InitializationOptions::from_value(None).debug_ast);
+
+    let options = InitializationOptions::from_value(Some(serde_json::json!({
+        "debugAst": true
+    })));
+    assert!(options.debug_ast);
+    assert!(options.known_blocks.is_none());
+}
+
+#[test]
+fn test_oversized_file_gets_block_checks_only() {
+    let options = InitializationOptions::from_value(Some(serde_json::json!({
+        "maxFileBytes": 64
+    })));
+    let config = options.into_config();
+    assert_eq!(config.max_file_bytes, 64);
+
+    let input = "let total = 1 + 'a'\n\nRouteInfo:\n    routed_gates = CX\n    realize_gate = 1 + 'a'\n\nstateinfo:\n    cost = 'free'\n";
+    let file = parse_file_with_config(input, &config).unwrap();
+    assert!(file.outline_only);
+    assert!(file.bindings.is_empty());
+    let kinds: Vec<_> = file.blocks.iter().map(|b| b.kind.as_str()).collect();
+    assert_eq!(kinds, vec!["RouteInfo", "stateinfo"]);
+
+    let diags = diagnose_with_config(input, &config);
+    let messages: Vec<_> = diags.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "File too large; deep analysis skipped.",
+            "Block 'stateinfo' should be Capitalized (e.g., 'StateInfo').",
+            "Missing mandatory block: 'TransitionInfo'.",
+        ]
+    );
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::INFORMATION));
+
+    // The default limit leaves ordinary files fully analysed
+    let file = parse_file(input).unwrap();
+    assert!(!file.outline_only);
+    assert_eq!(file.bindings.len(), 1);
+}
+
+#[test]
+fn test_configured_rule_severities() {
+    let input = format!(
+        "{}
+stateinfo:
+    cost = 1.0
+ArchInfo:
+",
+        MOCK_MANDATORY_BLOCKS
+    );
+    let file = parse_file(&input).unwrap();
+    let rule_severities = |config: &AnalysisConfig| -> Vec<_> {
+        check_semantics_with_config(&file, config)
+            .into_iter()
+            .filter(|d| d.code.is_some())
+            .map(|d| (d.message, d.severity))
+            .collect()
+    };
+
+    // Rules keep their default severity unless configured
+    let capitalization = "Block 'stateinfo' should be Capitalized (e.g., 'StateInfo').".to_string();
+    let empty = "Block 'ArchInfo' is empty.".to_string();
+    assert_eq!(
+        rule_severities(&AnalysisConfig::default()),
+        vec![
+            (capitalization.clone(), Some(DiagnosticSeverity::WARNING)),
+            (empty.clone(), Some(DiagnosticSeverity::WARNING)),
+        ]
+    );
+
+    let options = InitializationOptions::from_value(Some(serde_json::json!({
+        "severities": { "capitalization": "error", "emptyBlock": "off" }
+    })));
+    assert_eq!(
+        rule_severities(&options.into_config()),
+        vec![(capitalization, Some(DiagnosticSeverity::ERROR))]
+    );
+
+    let options = InitializationOptions::from_value(Some(serde_json::json!({
+        "severities": { "emptyBlock": "info" }
+    })));
+    assert_eq!(
+        options.severities.get("emptyBlock"),
+        Some(&RuleSeverity::Information)
+    );
+}
+
+#[test]
+fn test_invalid_severity_keeps_other_options() {
+    let options = InitializationOptions::from_value(Some(serde_json::json!({
+        "knownBlocks": ["MyInfo"],
+        "severities": {
+            "capitalization": "warn",
+            "capitalisation": "error",
+            "unusedLet": "off"
+        }
+    })));
+    assert_eq!(options.warnings.len(), 2, "Got: {:?}", options.warnings);
+    assert!(
+        options.warnings[0].starts_with("Ignoring severity for unknown rule 'capitalisation'.")
+    );
+    assert!(
+        options.warnings[1]
+            .starts_with("Ignoring severity for rule 'capitalization': unknown variant `warn`")
+    );
+
+    let config = options.into_config();
+    assert_eq!(config.known_blocks, vec!["MyInfo".to_string()]);
+    assert_eq!(
+        config.severities,
+        HashMap::from([("unusedLet".to_string(), RuleSeverity::Off)])
+    );
+}
+
+#[test]
+fn test_mandatory_blocks_can_be_disabled() {
+    let fragment = "RouteInfo:\n    routed_gates = CX\n    realize_gate = Vec()\n";
+    let missing = |config: &AnalysisConfig| {
+        diagnose_with_config(fragment, config)
+            .iter()
+            .filter(|d| d.message.starts_with("Missing mandatory block"))
+            .count()
+    };
+    assert_eq!(missing(&AnalysisConfig::default()), 1);
+
+    let options = InitializationOptions::from_value(Some(serde_json::json!({
+        "requireMandatoryBlocks": false
+    })));
+    assert_eq!(missing(&options.into_config()), 0);
+
+    // Only file names matching the glob are treated as fragments
+    let config = InitializationOptions::from_value(Some(serde_json::json!({
+        "fragmentGlob": "*.frag.qmrl"
+    })))
+    .into_config();
+    let url = |path| tower_lsp::lsp_types::Url::parse(path).unwrap();
+    assert_eq!(
+        missing(&config.for_document(&url("file:///lib/gates.frag.qmrl"))),
+        0
+    );
+    assert_eq!(
+        missing(&config.for_document(&url("file:///lib/main.qmrl"))),
+        1
+    );
+    assert_eq!(
+        missing(&config.for_document(&url("file:///frag.qmrl/main.qmrl"))),
+        1
+    );
+}
+
+#[test]
+fn test_diagnose_parsed_matches_diagnose() {
+    let input = format!(
+        "{}StateInfo:\n    cost = missing_name\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+    let config = AnalysisConfig::default();
+
+    let parsed = parse_file(&input);
+    let from_cache: Vec<_> = diagnose_parsed(&parsed, &config)
+        .into_iter()
+        .map(|d| (d.range, d.message))
+        .collect();
+    let fresh: Vec<_> = amaro_lsp::diagnose(&input)
+        .into_iter()
+        .map(|d| (d.range, d.message))
+        .collect();
+
+    assert!(!fresh.is_empty());
+    assert_eq!(from_cache, fresh);
+}
+
+#[test]
+fn test_tuple_projection_bounds() {
+    assert_eq!(infer("(1, 2.0).(1)"), Type::Float);
+
+    let diags = state_cost_diags("(1, 2.0).(3)");
+    let out_of_range: Vec<_> = diags
+        .iter()
+        .filter(|d| d.message == "Tuple has 2 elements; index 3 out of range")
+        .collect();
+    assert_eq!(out_of_range.len(), 1, "Got: {:?}", diags);
+    assert_eq!(out_of_range[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(out_of_range[0].range.start.character, 11);
+
+    // Tuples of unknown arity are not checked.
+    let diags = state_cost_diags("Transition.edge.(5)");
+    assert!(
+        !diags.iter().any(|d| d.message.contains("out of range")),
+        "Got: {:?}",
+        diags
+    );
+}
+
+/// A file whose `realize_gate` is `Some(literal)`, with `GateRealization`
+/// defined as `{u : Location, v : Location}`.
+fn struct_literal_source(literal: &str) -> String {
+    format!(
+        "RouteInfo:\n    GateRealization{{u : Location, v : Location}}\n    routed_gates = CX\n    realize_gate = Some({})\n{}",
+        literal, "TransitionInfo:\n    cost = 1.0\n    apply = []\n    get_transitions = []\n"
+    )
+}
+
+#[test]
+fn test_struct_literal_matches_definition() {
+    let input = struct_literal_source("GateRealization{u = Location(0), v = Location(1)}");
+    let diags = check_semantics(&parse_file(&input).unwrap());
+    assert!(diags.is_empty(), "Got: {:?}", diags);
+}
+
+#[test]
+fn test_struct_literal_field_errors() {
+    let input = struct_literal_source("GateRealization{u = 1.5, w = Location(1)}");
+    let errors: Vec<_> = check_semantics(&parse_file(&input).unwrap())
+        .into_iter()
+        .filter(|d| d.code.is_none())
+        .map(|d| d.message)
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "Field 'u' of 'GateRealization' expects Location, got Float.",
+            "Struct 'GateRealization' has no field 'w'.",
+            "Struct 'GateRealization' is missing field 'v'.",
+        ]
+    );
+}
+
+#[test]
+fn test_struct_literal_without_definition_is_unchecked() {
+    let input = format!(
+        "{}StateInfo:\n    cost = Transition{{anything = 1}}\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+    let file = parse_file(&input).unwrap();
+    let diags = check_semantics(&file);
+    assert!(
+        !diags
+            .iter()
+            .any(|d| d.message.contains("Struct 'Transition'")),
+        "Got: {:?}",
+        diags
+    );
+}
+
+#[test]
+fn test_power_inference() {
+    assert_eq!(infer("2 ** 3"), Type::Int);
+    assert_eq!(infer("2 ** 0.5"), Type::Float);
+    assert_eq!(infer("1.5 ** 2"), Type::Float);
+}
+
+#[test]
+fn test_range_inference_and_empty_range_warning() {
+    assert_eq!(infer("2..10"), Type::Vec(Box::new(Type::Int)));
+    assert_eq!(infer("0..1.5"), Type::Unknown);
+
+    let diags = state_cost_diags("(10..0).len()");
+    let empty: Vec<_> = diags
+        .iter()
+        .filter(|d| d.message.starts_with("Empty range"))
+        .collect();
+    assert_eq!(empty.len(), 1, "Got: {:?}", diags);
+    assert_eq!(
+        empty[0].message,
+        "Empty range: start 10 is greater than end 0."
+    );
+    assert_eq!(empty[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(empty[0].range.start.character, 12);
+
+    let diags = state_cost_diags("(0..10).len()");
+    assert!(!diags.iter().any(|d| d.message.starts_with("Empty range")));
+}
+
+#[test]
+fn test_tensor_product_types() {
+    assert_eq!(infer("CX ⊗ H"), Type::Gate);
+    assert_eq!(infer("CX tensor H ⊗ T"), Type::Gate);
+    assert!(cost_diags_with("if CX ⊗ H == H then 1.0 else 0.0", None).is_empty());
+
+    let diags = cost_diags_with("1 ⊗ H", None);
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Cannot apply '⊗' to Int and Gate.");
+    assert_eq!(infer("1 ⊗ H"), Type::Unknown);
+}
+
+#[test]
+fn test_binary_operator_operand_types() {
+    for (cost, message) in [
+        ("true + 1", "Cannot apply '+' to Bool and Int."),
+        (
+            "if 1 && 0 then 1.0 else 0.0",
+            "Cannot apply '&&' to Int and Int.",
+        ),
+        (
+            "if 1 < true then 1.0 else 0.0",
+            "Cannot apply '<' to Int and Bool.",
+        ),
+        // Unknown operands are left to the undefined-variable error
+        ("unknown_name + 1", "Undefined variable 'unknown_name'."),
+    ] {
+        let diags = cost_diags_with(cost, None);
+        assert_eq!(diags.len(), 1, "{}: {:?}", cost, diags);
+        assert_eq!(diags[0].message, message);
+    }
+
+    assert!(cost_diags_with("1 + 2.5", None).is_empty());
+    assert!(cost_diags_with("if 1 < 2 && true then 1.0 else 0.0", None).is_empty());
+    let diags = cost_diags_with("let q = State.map[Qubit(0)] / 2 in 1.0", None);
+    assert!(diags.is_empty(), "Got: {:?}", diags);
+}
+
+#[test]
+fn test_unused_let_binding_warning() {
+    let diags = cost_diags_with("let x = 1 in 2", rule_code("unusedLet"));
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Unused let binding 'x'.");
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(diags[0].range.start.line, 10);
+    assert_eq!(diags[0].range.start.character, 15);
+    assert_eq!(diags[0].range.end.character, 16);
+
+    assert!(cost_diags_with("let x = 1 in x + 1", rule_code("unusedLet")).is_empty());
+    assert!(
+        cost_diags_with("let x = 1 in if true then 0 else x", rule_code("unusedLet")).is_empty()
+    );
+    assert!(
+        cost_diags_with(
+            "let x = 1 in [1, 2].map(|y| -> y + x).len()",
+            rule_code("unusedLet")
+        )
+        .is_empty()
+    );
+    assert!(cost_diags_with("let _x = 1 in 2", rule_code("unusedLet")).is_empty());
+}
+
+#[test]
+fn test_shadowed_let_binding_is_unused() {
+    let diags = cost_diags_with(
+        "let x = 1 in [1, 2].map(|x| -> x).len()",
+        rule_code("unusedLet"),
+    );
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Unused let binding 'x'.");
+}
+
+#[test]
+fn test_let_chain_use_before_definition() {
+    let diags = state_cost_diags("let a = b + 1 in let b = 2 in a + b");
+    let errors: Vec<_> = diags
+        .iter()
+        .filter(|d| d.message.contains("before it is defined"))
+        .collect();
+    assert_eq!(errors.len(), 1, "Got: {:?}", diags);
+    assert_eq!(errors[0].message, "Cannot use 'b' before it is defined.");
+    assert_eq!(errors[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(errors[0].range.start.character, 19);
+    assert!(
+        !diags
+            .iter()
+            .any(|d| d.message.contains("Undefined variable"))
+    );
+
+    let diags = state_cost_diags("let a = 1 in let b = a + 1 in a + b");
+    assert!(
+        !diags
+            .iter()
+            .any(|d| d.message.contains("before it is defined")),
+        "Got: {:?}",
+        diags
+    );
+}
+
+#[test]
+fn test_unknown_gate_suggests_closest_literal() {
+    let routed = |gates: &str| {
+        let input = MOCK_MANDATORY_BLOCKS
+            .replace("routed_gates = CX", &format!("routed_gates = {}", gates));
+        check_semantics(&parse_file(&input).unwrap())
+    };
+
+    let diags = routed("CXX");
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Unknown gate 'CXX'. Did you mean 'CX'?");
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(diags[0].range.start.line, 2);
+    assert_eq!(diags[0].range.start.character, 19);
+
+    let diags = routed("[CX, Toffoli]");
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Unknown gate 'Toffoli'.");
+}
+
+#[test]
+fn test_non_standard_gate_literal_warns() {
+    let input = MOCK_MANDATORY_BLOCKS.replace("routed_gates = CX", "routed_gates = H");
+    let diags = check_semantics(&parse_file(&input).unwrap());
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].code, rule_code("nonstandardGate"));
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert!(diags[0].message.contains("not a recognized standard gate"));
+}
+
+#[test]
+fn test_undefined_variable_suggestions() {
+    for (cost, message) in [
+        (
+            "let consistent = 1 in consistnet",
+            "Undefined variable 'consistnet'. Did you mean 'consistent'?",
+        ),
+        (
+            "Stat.map",
+            "Undefined variable 'Stat'. Did you mean 'State'?",
+        ),
+        (
+            "completely_unrelated",
+            "Undefined variable 'completely_unrelated'.",
+        ),
+        ("q", "Undefined variable 'q'."),
+    ] {
+        let diags = cost_diags_with(cost, None);
+        assert_eq!(diags.len(), 1, "{}: {:?}", cost, diags);
+        assert_eq!(diags[0].message, message);
+    }
+}
+
+#[test]
+fn test_calling_non_function_is_error() {
+    let diags = cost_diags_with("1(2)", None);
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Value of type Int is not callable.");
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(diags[0].range.start.character, 11);
+
+    let diags = cost_diags_with("let c = 0.5 in c(1)", None);
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Value of type Float is not callable.");
+
+    assert!(cost_diags_with("Arch.implementation(1)", None).is_empty());
+}
+
+#[test]
+fn test_only_rotation_gates_take_an_angle() {
+    let diags = cost_diags_with("H(0.5)", None);
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Value of type Gate is not callable.");
+
+    assert_eq!(infer("RX(1.57)"), Type::Gate);
+    assert!(state_cost_diags("if RX(1.57) == RZ(0.5) then 1.0 else 2.0").is_empty());
+
+    let diags = state_cost_diags("if RY() == H then 1.0 else 2.0");
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "RY expects 1 argument, got 0.");
+
+    let diags = state_cost_diags("if RX == H then 1.0 else 2.0");
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::ERROR));
+}
+
+#[test]
+fn test_vec_constructor_infers_element_type() {
+    let vec_of = |t: Type| Type::Vec(Box::new(t));
+
+    assert_eq!(infer("Vec()"), vec_of(Type::Unknown));
+    assert_eq!(infer("Vec(Location(0))"), vec_of(Type::Location));
+    assert_eq!(
+        infer("Vec(Location(0)).push(Location(1))"),
+        vec_of(Type::Location)
+    );
+    assert_eq!(infer("Vec(Vec(1, 2))"), vec_of(vec_of(Type::Int)));
+
+    let diags = state_cost_diags("(Vec(Location(0)).push(1.5)).len()");
+    assert!(
+        diags
+            .iter()
+            .any(|d| d.message == "Argument 1 expected type 'Location' but got 'Float'."),
+        "Got: {:?}",
+        diags
+    );
+}
+
+#[test]
+fn test_architecture_dimensions_must_be_positive_integers() {
+    let errors = |fields: &str| {
+        let input = format!("{}Architecture[\n{}\n]\n", MOCK_MANDATORY_BLOCKS, fields);
+        check_semantics(&parse_file(&input).unwrap())
+            .into_iter()
+            .filter(|d| d.code.is_none())
+            .map(|d| d.message)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        errors("    width = -1"),
+        vec!["Architecture width must be a positive integer."]
+    );
+    assert_eq!(
+        errors("    rows = 0"),
+        vec!["Architecture rows must be a positive integer."]
+    );
+    assert_eq!(
+        errors("    width = 'ten'"),
+        vec!["Architecture width must be Int, got String."]
+    );
+    assert_eq!(
+        errors("    height = 2.5"),
+        vec!["Architecture height must be Int, got Float."]
+    );
+
+    assert!(errors("    width = 10\n    height = 4").is_empty());
+    assert!(errors("    width = 2 - 3").is_empty());
+    assert!(errors("    name = -1").is_empty());
+}
+
+#[test]
+fn test_division_by_zero_literal_warns() {
+    let diags = cost_diags_with("let x = 4 in x / 0", rule_code("divisionByZero"));
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(diags[0].range.start.character, 24);
+
+    assert_eq!(
+        cost_diags_with("let x = 4 in x % 0", rule_code("divisionByZero")).len(),
+        1
+    );
+    assert!(
+        cost_diags_with(
+            "let x = 4 in let n = 0 in x / n",
+            rule_code("divisionByZero")
+        )
+        .is_empty()
+    );
+    assert!(cost_diags_with("let x = 4 in x / 10", rule_code("divisionByZero")).is_empty());
+}
+
+#[test]
+fn test_static_list_index_bounds() {
+    let diags = cost_diags_with("[1.0, 2.0][5]", None);
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(
+        diags[0].message,
+        "Index 5 out of bounds for list of length 2."
+    );
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(diags[0].range.start.character, 22);
+    assert_eq!(cost_diags_with("[1.0, 2.0][2]", None).len(), 1);
+
+    assert!(cost_diags_with("[1.0, 2.0][1]", None).is_empty());
+    assert!(cost_diags_with("let i = 5 in [1.0, 2.0][i]", None).is_empty());
+    assert!(cost_diags_with("(Vec(1.0))[5]", None).is_empty());
+    assert_eq!(infer("[1.0, 2.0][1]"), Type::Float);
+}
+
+#[test]
+fn test_chained_comparison_warns() {
+    let diags = cost_diags_with(
+        "if 1 < 2 < 3 then 1.0 else 0.0",
+        rule_code("chainedComparison"),
+    );
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(
+        diags[0].message,
+        "Chained comparison '1 < 2 < 3' may not do what you expect; use '&&'."
+    );
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(diags[0].range.start.character, 14);
+
+    // The warning replaces the Bool-vs-Int operand error on the same range
+    let all = state_cost_diags("if 1 < 2 < 3 then 1.0 else 0.0");
+    assert_eq!(all.len(), 1, "Got: {:?}", all);
+
+    // Parentheses make the grouping explicit
+    let all = state_cost_diags("if (1 > 2) == true then 1.0 else 0.0");
+    assert!(all.is_empty(), "Got: {:?}", all);
+
+    assert!(
+        cost_diags_with(
+            "if 1 < 2 && 2 < 3 then 1.0 else 0.0",
+            rule_code("chainedComparison")
+        )
+        .is_empty()
+    );
+    assert!(
+        cost_diags_with(
+            "if 1 + 2 < 3 then 1.0 else 0.0",
+            rule_code("chainedComparison")
+        )
+        .is_empty()
+    );
+}
+
+#[test]
+fn test_field_reading_itself_warns() {
+    let warnings = |source: &str| {
+        let input = format!(
+            "RouteInfo:\n    routed_gates = CX\n    realize_gate = []\n{}",
+            source
+        );
+        check_semantics(&parse_file(&input).unwrap())
+            .into_iter()
+            .filter(|d| d.code == rule_code("cyclicReference"))
+            .collect::<Vec<_>>()
+    };
+
+    let diags = warnings(
+        "TransitionInfo:\n    get_transitions = []\n    apply = []\n    cost = Transition.cost + 1.0\n",
+    );
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(
+        diags[0].message,
+        "Cyclic reference: 'cost' depends on itself."
+    );
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(diags[0].range.start.line, 6);
+    assert_eq!(diags[0].range.start.character, 11);
+
+    // Reading a different field of the same block is fine
+    let diags = warnings(
+        "TransitionInfo:\n    get_transitions = []\n    apply = []\n    cost = Transition.weight\n",
+    );
+    assert!(diags.is_empty(), "Got: {:?}", diags);
+
+    // So is shadowing the context variable
+    let diags = warnings(
+        "TransitionInfo:\n    get_transitions = []\n    apply = []\n    cost = map(|Transition| -> Transition.cost, [])\n",
+    );
+    assert!(diags.is_empty(), "Got: {:?}", diags);
+}
+
+#[test]
+fn test_empty_block_warns() {
+    let warnings = |source: &str| {
+        let input = format!("{}\n{}", MOCK_MANDATORY_BLOCKS, source);
+        check_semantics(&parse_file(&input).unwrap())
+            .into_iter()
+            .filter(|d| d.code == rule_code("emptyBlock"))
+            .collect::<Vec<_>>()
+    };
+
+    let diags = warnings("StateInfo:\nArchInfo:\n    Arch{width : Int}\n");
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Block 'StateInfo' is empty.");
+    assert_eq!(diags[0].severity, Some(DiagnosticSeverity::WARNING));
+
+    let diags = warnings("Architecture[]\n");
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Block 'Architecture' is empty.");
+
+    assert!(warnings("Architecture[name='test']\n").is_empty());
+}
+
+#[test]
+fn test_option_field_requires_some() {
+    assert_eq!(
+        infer("Some(1)"),
+        Type::Option(Box::new(Type::Int)),
+        "Some(x) should wrap the type of x"
+    );
+
+    let errors = |value: &str| {
+        let input = format!(
+            "RouteInfo:\n    GateRealization{{path : Option<Vec<Location>>}}\n    routed_gates = CX\n    realize_gate = let path = Vec(Location(0)) in GateRealization{{path = {}}}\n{}",
+            value, "TransitionInfo:\n    cost = 1.0\n    apply = []\n    get_transitions = []\n"
+        );
+        check_semantics(&parse_file(&input).unwrap())
+            .into_iter()
+            .filter(|d| d.code.is_none())
+            .map(|d| d.message)
+            .collect::<Vec<_>>()
+    };
+    assert!(errors("Some(path)").is_empty());
+    assert!(errors("None").is_empty());
+    assert_eq!(
+        errors("path"),
+        vec!["Expected Option<Vec<Location>>, got Vec<Location>; wrap in Some(...)."]
+    );
+
+    let input = struct_literal_source("GateRealization{u = Some(Location(0)), v = Location(1)}");
+    let diags = check_semantics(&parse_file(&input).unwrap());
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(
+        diags[0].message,
+        "Expected Location, got Option<Location>; the value may be None."
+    );
+}
+
+#[test]
+fn test_negation_types() {
+    assert_eq!(infer("-3.0"), Type::Float);
+    assert_eq!(infer("-3"), Type::Int);
+    assert_eq!(infer("- 2.5 * 2.0"), Type::Float);
+
+    let diags = cost_diags_with("-'a'", None);
+    assert_eq!(diags.len(), 1, "Got: {:?}", diags);
+    assert_eq!(diags[0].message, "Cannot apply '-' to String.");
+    assert!(cost_diags_with("let x = 2 in -x", None).is_empty());
+}
+
+#[test]
+fn test_context_variables_scoped_by_block() {
+    let errors = |input: &str| {
+        check_semantics(&parse_file(input).unwrap())
+            .into_iter()
+            .filter(|d| d.code.is_none())
+            .map(|d| d.message)
+            .collect::<Vec<_>>()
+    };
+
+    let input = format!(
+        "{}ArchInfo:\n    Arch{{width : Int}}\n    width = step\nStateInfo:\n    cost = if Gate.qubits[0] == Transition.x then 1.0 else step\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+    assert_eq!(
+        errors(&input),
+        vec![
+            "'step' is not available in ArchInfo.",
+            "'Gate' is not available in StateInfo.",
+            "'Transition' is not available in StateInfo.",
+        ]
+    );
+
+    // Each variable is fine where it belongs, and in non-Info blocks
+    let input = format!(
+        "{}StateInfo:\n    cost = if State.map() == State.map() then step else 0.0\nStep[\n    n = Gate.qubits\n]\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+    assert!(errors(&input).is_empty());
+
+    // A struct definition of the same name is visible everywhere
+    let input = format!(
+        "{}ArchInfo:\n    State{{n : Int}}\n    x = State{{n = 1}}\n",
+        MOCK_MANDATORY_BLOCKS
+    );
+    assert!(errors(&input).is_empty());
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "serde")]
+
+use amaro_lsp::ast::to_json;
+use amaro_lsp::parser::parse_file;
+
+#[test]
+fn test_to_json_round_trips_block_kinds() {
+    let input = "Architecture[name='grid']\n\nStateInfo:\n    cost = 1 + 2\n";
+    let file = parse_file(input).unwrap();
+
+    let json: serde_json::Value = serde_json::from_str(&to_json(&file)).unwrap();
+    let kinds: Vec<&str> = json["blocks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| b["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, vec!["Architecture", "StateInfo"]);
+
+    let cost = &json["blocks"][1]["content"]["Fields"][0]["Field"]["value"];
+    assert_eq!(cost["kind"]["BinaryOp"]["op"], "Add");
+    assert_eq!(cost["range"]["start"]["line"], 3);
+}
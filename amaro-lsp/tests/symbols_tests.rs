@@ -36,7 +36,7 @@ fn test_scope_management() {
     table.exit_scope();
 
     assert!(matches!(table.lookup("x"), Some(Type::Int)));
-    assert!(matches!(table.lookup("y"), None));
+    assert!(table.lookup("y").is_none());
 }
 
 #[test]
@@ -74,16 +74,131 @@ fn test_nested_scopes() {
 
     assert!(matches!(table.lookup("a"), Some(Type::Int)));
     assert!(matches!(table.lookup("b"), Some(Type::Float)));
-    assert!(matches!(table.lookup("c"), None));
+    assert!(table.lookup("c").is_none());
 
     table.exit_scope();
 
     assert!(matches!(table.lookup("a"), Some(Type::Int)));
-    assert!(matches!(table.lookup("b"), None));
+    assert!(table.lookup("b").is_none());
 }
 
 #[test]
 fn test_lookup_nonexistent() {
     let table = SymbolTable::new();
-    assert!(matches!(table.lookup("nonexistent"), None));
+    assert!(table.lookup("nonexistent").is_none());
+}
+
+#[test]
+fn test_numeric_and_collection_builtins() {
+    let table = SymbolTable::new();
+
+    for name in [
+        "consistent",
+        "to_2d",
+        "combinations",
+        "max",
+        "min",
+        "abs",
+        "dist",
+    ] {
+        assert!(
+            matches!(table.lookup(name), Some(Type::Function { .. })),
+            "'{}' should be a builtin function",
+            name
+        );
+    }
+
+    assert!(matches!(
+        table.lookup("max"),
+        Some(Type::Function { variadic: true, .. })
+    ));
+    assert!(matches!(
+        table.lookup("dist"),
+        Some(Type::Function { params, .. }) if params.len() == 2
+    ));
+}
+
+#[test]
+fn test_type_display() {
+    let location = || Type::Location;
+    let vec_of = |t: Type| Type::Vec(Box::new(t));
+
+    assert_eq!(Type::Int.to_string(), "Int");
+    assert_eq!(vec_of(vec_of(location())).to_string(), "Vec<Vec<Location>>");
+    assert_eq!(
+        Type::Option(Box::new(Type::Qubit)).to_string(),
+        "Option<Qubit>"
+    );
+    assert_eq!(
+        Type::Tuple(vec![location(), location()]).to_string(),
+        "(Location, Location)"
+    );
+    assert_eq!(
+        Type::Struct {
+            name: "Transition".to_string(),
+            fields: Default::default(),
+        }
+        .to_string(),
+        "Transition"
+    );
+}
+
+#[test]
+fn test_function_type_display() {
+    let function = |params: Vec<Type>, return_type: Type, variadic: bool| Type::Function {
+        params,
+        return_type: Box::new(return_type),
+        variadic,
+    };
+
+    assert_eq!(
+        function(vec![Type::Qubit], Type::Location, false).to_string(),
+        "Qubit -> Location"
+    );
+    assert_eq!(
+        function(
+            vec![Type::Location, Type::Int],
+            Type::Vec(Box::new(Type::Location)),
+            false
+        )
+        .to_string(),
+        "(Location, Int) -> Vec<Location>"
+    );
+    assert_eq!(
+        function(
+            vec![Type::Tuple(vec![Type::Int, Type::Int])],
+            Type::Int,
+            false
+        )
+        .to_string(),
+        "((Int, Int)) -> Int"
+    );
+    assert_eq!(
+        function(vec![], Type::Vec(Box::new(Type::Unknown)), true).to_string(),
+        "(...) -> Vec<Unknown>"
+    );
+    assert_eq!(
+        function(
+            vec![function(vec![Type::Int], Type::Int, false)],
+            Type::Bool,
+            false
+        )
+        .to_string(),
+        "(Int -> Int) -> Bool"
+    );
+}
+
+#[test]
+fn test_builtin_docs() {
+    let table = SymbolTable::new();
+    assert_eq!(
+        table.doc("map"),
+        Some("Applies a function to every element of a vector.")
+    );
+
+    // Every builtin function is documented; context values are not
+    for name in SymbolTable::builtin_functions().keys() {
+        assert!(table.doc(name).is_some(), "'{}' has no description", name);
+    }
+    assert_eq!(table.doc("Arch"), None);
 }
@@ -1,4 +1,7 @@
+use amaro_lsp::ast::{BinaryOperator, ExprKind};
+use amaro_lsp::parser::config::rule_code;
 use amaro_lsp::parser::{check_semantics, parse_file};
+use tower_lsp::lsp_types::Position;
 
 #[test]
 fn test_advanced_features_and_vectors() {
@@ -38,16 +41,107 @@ fn test_advanced_features_and_vectors() {
         cost = 1.0
     "#;
 
-    let file = parse_file(&input).unwrap();
+    let file = parse_file(input).unwrap();
     let diags = check_semantics(&file);
 
-    // 3. Assert NO Errors
+    // 3. Assert NO Errors; only the unused `popped` binding is reported
     for diag in &diags {
         println!("Diagnostic: {:?}", diag);
     }
-    assert!(
-        diags.is_empty(),
-        "Expected 0 diagnostics, found {}",
+    assert_eq!(
+        diags.len(),
+        1,
+        "Expected 1 diagnostic, found {}",
         diags.len()
     );
+    assert_eq!(diags[0].code, rule_code("unusedLet"));
+    assert!(diags[0].message.contains("'popped'"));
+}
+
+const TRANSITION_SOURCE: &str = "TransitionInfo:
+    Transition{edge : (Location, Location)}
+    get_transitions = map(|x| -> Transition{edge = x}, Arch.edges())
+    apply = value_swap(Transition.edge.(0), Transition.edge.(1))
+    cost = 0.0
+";
+
+#[test]
+fn test_transition_fields_are_retained() {
+    let file = parse_file(TRANSITION_SOURCE).unwrap();
+    let block = file.block("TransitionInfo").expect("TransitionInfo block");
+
+    let cost = block.field("cost").expect("cost field");
+    assert!(matches!(cost.value.kind, ExprKind::FloatLiteral(v) if v == 0.0));
+    assert_eq!(cost.key_range.start, Position::new(4, 4));
+    assert_eq!(cost.value.range.start, Position::new(4, 11));
+
+    let apply = block.field("apply").expect("apply field");
+    let ExprKind::FunctionCall { function, args } = &apply.value.kind else {
+        panic!("apply parsed as {:?}", apply.value.kind);
+    };
+    assert!(matches!(&function.kind, ExprKind::Identifier(name) if name == "value_swap"));
+    assert_eq!(args.len(), 2);
+    assert!(matches!(
+        &args[1].kind,
+        ExprKind::Projection { index: 1, tuple } if matches!(
+            &tuple.kind,
+            ExprKind::FieldAccess { field, .. } if field == "edge"
+        )
+    ));
+
+    let transitions = block
+        .field("get_transitions")
+        .expect("get_transitions field");
+    assert!(matches!(
+        &transitions.value.kind,
+        ExprKind::FunctionCall { args, .. } if matches!(args[0].kind, ExprKind::Lambda { .. })
+    ));
+    assert!(block.field("missing").is_none());
+}
+
+#[test]
+fn test_binary_field_value_spans() {
+    let file = parse_file("StateInfo:\n    cost = 1.0 + step * 2.0\n").unwrap();
+    let cost = file.block("StateInfo").unwrap().field("cost").unwrap();
+
+    let ExprKind::BinaryOp { op, left, right } = &cost.value.kind else {
+        panic!("cost parsed as {:?}", cost.value.kind);
+    };
+    assert_eq!(*op, BinaryOperator::Add);
+    assert!(matches!(left.kind, ExprKind::FloatLiteral(v) if v == 1.0));
+    assert_eq!(left.range.start, Position::new(1, 11));
+    assert!(matches!(
+        right.kind,
+        ExprKind::BinaryOp {
+            op: BinaryOperator::Mul,
+            ..
+        }
+    ));
+    assert_eq!(right.range.start, Position::new(1, 17));
+}
+
+fn apply_diagnostics(apply: &str) -> Vec<String> {
+    let input = format!(
+        "RouteInfo:\n    routed_gates = CX\n    realize_gate = Vec()\n\
+         TransitionInfo:\n    get_transitions = Vec()\n    apply = {}\n    cost = 0.0\n",
+        apply
+    );
+    check_semantics(&parse_file(&input).unwrap())
+        .into_iter()
+        .map(|d| d.message)
+        .collect()
+}
+
+#[test]
+fn test_apply_must_be_transition_application() {
+    assert!(apply_diagnostics("identity_application(step)").is_empty());
+    assert!(apply_diagnostics("value_swap(Transition.edge.(0), Transition.edge.(1))").is_empty());
+
+    assert_eq!(
+        apply_diagnostics("0.0"),
+        vec![
+            "'apply' should be a transition application such as \
+             identity_application(step), got Float."
+        ]
+    );
 }
@@ -1,4 +1,7 @@
-use amaro_lsp::parser::utils::{byte_to_position, calc_range};
+use amaro_lsp::parser::utils::{
+    apply_change, byte_to_position, calc_range, identifier_at, position_to_byte,
+};
+use tower_lsp::lsp_types::{Range, TextDocumentContentChangeEvent};
 
 #[test]
 fn test_byte_to_position_single_line() {
@@ -37,3 +40,138 @@ fn test_calc_range() {
     assert_eq!(range.end.line, 0);
     assert_eq!(range.end.character, 15);
 }
+
+#[test]
+fn test_position_to_byte() {
+    let text = "Line1\nLine2\nLine3";
+    let pos = |line, character| tower_lsp::lsp_types::Position { line, character };
+    assert_eq!(position_to_byte(text, pos(0, 0)), 0);
+    assert_eq!(position_to_byte(text, pos(1, 2)), 8);
+    // Clamped to the end of the line / file
+    assert_eq!(position_to_byte(text, pos(1, 99)), 11);
+    assert_eq!(position_to_byte(text, pos(9, 0)), text.len());
+}
+
+#[test]
+fn test_identifier_at() {
+    let text = "x = all_paths(arch)";
+    assert_eq!(identifier_at(text, 6), Some((4, 13)));
+    // Cursor right after the identifier still touches it
+    assert_eq!(identifier_at(text, 13), Some((4, 13)));
+    assert_eq!(identifier_at(text, 2), None);
+}
+
+#[test]
+fn test_apply_change_incremental() {
+    let pos = |line, character| tower_lsp::lsp_types::Position { line, character };
+    let mut text = String::from("RouteInfo:\n    cost = 1\n");
+
+    // Replace "1" with "2.5"
+    apply_change(
+        &mut text,
+        &TextDocumentContentChangeEvent {
+            range: Some(Range {
+                start: pos(1, 11),
+                end: pos(1, 12),
+            }),
+            range_length: None,
+            text: "2.5".to_string(),
+        },
+    );
+    assert_eq!(text, "RouteInfo:\n    cost = 2.5\n");
+
+    // Pure insertion at the end of the document
+    apply_change(
+        &mut text,
+        &TextDocumentContentChangeEvent {
+            range: Some(Range {
+                start: pos(2, 0),
+                end: pos(2, 0),
+            }),
+            range_length: None,
+            text: "StateInfo:\n".to_string(),
+        },
+    );
+    assert_eq!(text, "RouteInfo:\n    cost = 2.5\nStateInfo:\n");
+}
+
+#[test]
+fn test_apply_change_full_replace() {
+    let mut text = String::from("RouteInfo:\n");
+    apply_change(
+        &mut text,
+        &TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: "StateInfo:\n".to_string(),
+        },
+    );
+    assert_eq!(text, "StateInfo:\n");
+}
+
+#[test]
+fn test_byte_to_position_counts_utf16_units() {
+    // 'é' is 2 bytes / 1 UTF-16 unit
+    let text = "café = x";
+    assert_eq!(byte_to_position(text, text.find('=').unwrap()), (0, 5));
+
+    // '⊗' is 3 bytes / 1 UTF-16 unit
+    let text = "a ⊗ b";
+    assert_eq!(byte_to_position(text, text.find('b').unwrap()), (0, 4));
+
+    // '😀' is 4 bytes / 2 UTF-16 units (surrogate pair)
+    let text = "// 😀\nx = '😀' y";
+    assert_eq!(byte_to_position(text, text.find('y').unwrap()), (1, 9));
+}
+
+#[test]
+fn test_position_to_byte_counts_utf16_units() {
+    let pos = |line, character| tower_lsp::lsp_types::Position { line, character };
+    let text = "x = '😀' y\ncafé";
+    assert_eq!(position_to_byte(text, pos(0, 9)), text.find('y').unwrap());
+    assert_eq!(position_to_byte(text, pos(1, 4)), text.len());
+
+    // Round trip through both conversions
+    for (i, _) in text.char_indices() {
+        let (line, character) = byte_to_position(text, i);
+        assert_eq!(position_to_byte(text, pos(line, character)), i);
+    }
+}
+
+#[test]
+fn test_position_to_byte_inverts_byte_to_position() {
+    let pos = |line, character| tower_lsp::lsp_types::Position { line, character };
+    for text in ["cost = 1.0\nmap = x\n", "x = 'é😀'\ny"] {
+        // Every char boundary survives the round trip
+        for (i, _) in text.char_indices().chain([(text.len(), ' ')]) {
+            let (line, character) = byte_to_position(text, i);
+            assert_eq!(position_to_byte(text, pos(line, character)), i);
+        }
+
+        // And every position that starts a character does too
+        for (line, line_text) in text.split('\n').enumerate() {
+            let mut character = 0;
+            for c in line_text.chars() {
+                let p = pos(line as u32, character);
+                assert_eq!(
+                    byte_to_position(text, position_to_byte(text, p)),
+                    (p.line, p.character)
+                );
+                character += c.len_utf16() as u32;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_crlf_line_endings() {
+    let pos = |line, character| tower_lsp::lsp_types::Position { line, character };
+    let text = "Line1\r\nLine2\r\n";
+
+    // End of line 1 excludes the '\r'
+    assert_eq!(byte_to_position(text, 5), (0, 5));
+    assert_eq!(byte_to_position(text, 6), (0, 5));
+    assert_eq!(byte_to_position(text, 7), (1, 0));
+    assert_eq!(position_to_byte(text, pos(0, 99)), 5);
+    assert_eq!(position_to_byte(text, pos(1, 5)), 12);
+}